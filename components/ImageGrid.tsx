@@ -386,7 +386,7 @@ export function ImageGrid() {
     const result = await open({
       multiple: true,
       directory: false,
-      filters: [{ name: "Images", extensions: ["png", "jpg", "jpeg", "webp", "gif", "bmp", "tiff"] }],
+      filters: [{ name: "Images", extensions: ["png", "jpg", "jpeg", "webp", "gif", "bmp", "tif", "tiff", "avif"] }],
     });
     const picked = Array.isArray(result) ? result : result ? [result] : [];
     if (picked.length === 0) return;
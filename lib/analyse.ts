@@ -1,11 +1,150 @@
 import { invoke } from "@tauri-apps/api/core";
-import { merge, type AnalysedFile, type LoadedFile, type MediaAnalysis } from "./types";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import { merge, type AnalyseOptions, type AnalysedFile, type AnalysisDoneEvent, type AnalysisPage, type AnalysisProfile, type AnalysisResultEvent, type AnalysisSession, type BatchSummary, type BurstReport, type LoadedFile, type FileAnalysedEvent, type HashAlgorithm, type IntegrityCheck, type MediaAnalysis, type PrivacyReport, type RenameOutcome, type RenamePlan, type SimilarMatch, type TagWriteOutcome } from "./types";
 
-export async function analyseMedia(files: LoadedFile[]): Promise<LoadedFile[]> {
-  const analysis = (await invoke("analyse_file", { files })) as MediaAnalysis[];
+export async function analyseMedia(files: LoadedFile[], options?: AnalyseOptions, profile?: AnalysisProfile): Promise<LoadedFile[]> {
+  const analysis = (await invoke("analyse_file", { files, options, profile })) as MediaAnalysis[];
   if (analysis.length !== files.length) throw new Error(`analyse_file returned ${analysis.length} analyses for ${files.length} files`);
   return analysis.map((a, i) => {
     return {...files[i], analysis: a}
   });
 }
 
+// One file (drag-drop, re-analysis after an edit); rejects with the error instead of
+// needing a one-element batch.
+export async function analyseOne(file: LoadedFile, options?: AnalyseOptions, profile?: AnalysisProfile): Promise<LoadedFile> {
+  const analysis = (await invoke("analyse_one", { file, options, profile })) as MediaAnalysis;
+  return {...file, analysis};
+}
+
+// For contents without a filesystem path (drag data, fetched blobs); `name` must carry the
+// extension. Results are not cached.
+export async function analyseBytes(name: string, data: ArrayBuffer, options?: AnalyseOptions, profile?: AnalysisProfile): Promise<MediaAnalysis> {
+  let binary = "";
+  const bytes = new Uint8Array(data);
+  for (let i = 0; i < bytes.length; i += 0x8000) {
+    binary += String.fromCharCode(...bytes.subarray(i, i + 0x8000));
+  }
+  const file = { name, data_b64: btoa(binary) };
+  return (await invoke("analyse_bytes", { file, options, profile })) as MediaAnalysis;
+}
+
+// For very large batches: every page but the last arrives through onAnalysisPage; the
+// last page (results for the trailing files) is the resolved value.
+export async function analyseMediaPaged(files: LoadedFile[], pageSize: number, options?: AnalyseOptions, profile?: AnalysisProfile): Promise<MediaAnalysis[]> {
+  return (await invoke("analyse_file_paged", { files, pageSize, options, profile })) as MediaAnalysis[];
+}
+
+export function onAnalysisPage(cb: (page: AnalysisPage) => void): Promise<UnlistenFn> {
+  return listen<AnalysisPage>("analysis_page", (event) => cb(event.payload));
+}
+
+// Resolves with the job id right away; results arrive through onAnalysisResult in
+// completion order, then onAnalysisDone. Subscribe before calling to miss nothing.
+export async function analyseMediaStreamed(files: LoadedFile[], options?: AnalyseOptions, profile?: AnalysisProfile): Promise<number> {
+  return (await invoke("analyse_file_streamed", { files, options, profile })) as number;
+}
+
+// Stops a streamed job: unstarted files come back with error "analysis cancelled", running
+// ones skip or abandon their AI request. onAnalysisDone still fires.
+export async function cancelAnalysis(jobId: number): Promise<void> {
+  await invoke("cancel_analysis", { jobId });
+}
+
+export function onAnalysisResult(cb: (result: AnalysisResultEvent) => void): Promise<UnlistenFn> {
+  return listen<AnalysisResultEvent>("analyse://result", (event) => cb(event.payload));
+}
+
+export function onAnalysisDone(cb: (done: AnalysisDoneEvent) => void): Promise<UnlistenFn> {
+  return listen<AnalysisDoneEvent>("analyse://done", (event) => cb(event.payload));
+}
+
+// Dry run: maps each file to its path under `template` (e.g. "{date}_{topic}_{n}") without renaming.
+export async function planRenames(files: LoadedFile[], template: string): Promise<RenamePlan[]> {
+  return (await invoke("plan_file_renames", { files, template })) as RenamePlan[];
+}
+
+// Performs a plan from planRenames; if any rename fails, the completed ones are undone.
+export async function executeRenames(plan: RenamePlan[]): Promise<RenameOutcome[]> {
+  return (await invoke("execute_file_renames", { plan })) as RenameOutcome[];
+}
+
+// Watches `path` recursively; each new/changed media file arrives through onFileAnalysed.
+export async function startFileWatcher(path: string, options?: AnalyseOptions): Promise<void> {
+  await invoke("start_file_watcher", { path, options });
+}
+
+export async function stopFileWatcher(): Promise<void> {
+  await invoke("stop_file_watcher");
+}
+
+export function onFileAnalysed(cb: (e: FileAnalysedEvent) => void): Promise<UnlistenFn> {
+  return listen<FileAnalysedEvent>("file_analysed", (event) => cb(event.payload));
+}
+
+// Saves the scan (files with their analyses) as one JSON document at `path`.
+export async function exportAnalyses(files: LoadedFile[], path: string): Promise<void> {
+  await invoke("export_analysis_session", { files, path });
+}
+
+export async function importAnalyses(path: string): Promise<AnalysisSession> {
+  return (await invoke("import_analysis_session", { path })) as AnalysisSession;
+}
+
+// Result stored by a previous analyseMedia run; null if missing or the file changed since.
+export async function getCachedAnalysis(path: string): Promise<MediaAnalysis | null> {
+  return (await invoke("get_cached_analysis", { path })) as MediaAnalysis | null;
+}
+
+export async function clearCachedAnalysis(path: string): Promise<boolean> {
+  return (await invoke("clear_cached_analysis", { path })) as boolean;
+}
+
+// Media files under `path`, honouring .mediaignore files (gitignore syntax) inside it.
+export async function scanDirectory(path: string): Promise<{ name: string; path: string }[]> {
+  return (await invoke("scan_media_directory", { path })) as { name: string; path: string }[];
+}
+
+// Re-hashes the file (no analysis) and compares with expectedHash, e.g. after a copy.
+export async function verifyFileIntegrity(path: string, expectedHash: string, algorithm: HashAlgorithm = "sha256"): Promise<IntegrityCheck> {
+  return (await invoke("verify_file_integrity", { path, expectedHash, algorithm })) as IntegrityCheck;
+}
+
+// Flags GPS data and likely people in analysed files; runs on the results, no re-analysis.
+export async function detectPrivateData(files: LoadedFile[]): Promise<PrivacyReport[]> {
+  return (await invoke("detect_private_data", { files })) as PrivacyReport[];
+}
+
+// Dashboard stats for a batch of results; pure aggregation, nothing is re-read.
+export async function summarize(analyses: MediaAnalysis[]): Promise<BatchSummary> {
+  return (await invoke("summarize", { analyses })) as BatchSummary;
+}
+
+// Burst shots (same camera, a second or less apart) among analysed images, with the
+// sharpest frame of each.
+export async function detectBursts(analyses: MediaAnalysis[]): Promise<BurstReport> {
+  return (await invoke("detect_bursts", { analyses })) as BurstReport;
+}
+
+// Analyses whose phash is within maxDistance bits (of 64) of the image at referencePath,
+// closest first. Only Thorough analyses have a phash.
+export async function findSimilar(referencePath: string, analyses: MediaAnalysis[], maxDistance: number): Promise<SimilarMatch[]> {
+  return (await invoke("find_similar", { referencePath, analyses, maxDistance })) as SimilarMatch[];
+}
+
+// Numbered shots taken seconds apart (IMG_0041, IMG_0042, ...) get a shared
+// image.sequence_id; files come back in the order given.
+export async function detectSequences(files: LoadedFile[]): Promise<{ name: string; path: string; analysis: MediaAnalysis }[]> {
+  return (await invoke("detect_sequences", { files })) as { name: string; path: string; analysis: MediaAnalysis }[];
+}
+
+// Lossless copy of a JPEG/PNG without EXIF/XMP/text metadata; destPath may equal path.
+export async function stripExif(path: string, destPath: string): Promise<void> {
+  await invoke("strip_exif", { path, destPath });
+}
+
+// Adds tags to the XMP/IPTC keywords embedded in each JPEG/PNG, in place; other formats
+// come back with written: false and the reason.
+export async function writeImageTags(paths: string[], tags: string[]): Promise<TagWriteOutcome[]> {
+  return (await invoke("write_image_tags", { paths, tags })) as TagWriteOutcome[];
+}
@@ -15,12 +15,18 @@ export type LoadedFile = {
   analysis?: MediaAnalysis;
 };
 
+export type MetaFileType = "image" | "video" | "pdf" | "audio" | "office" | "email" | "other";
+
 export type Metadata = {
-  file_type: string;
+  file_type: MetaFileType;
   mime: string | null;
   size_bytes: number | null;
   created_at: string | null;   // RFC 3339 string
   modified_at: string | null;  // RFC 3339 string
+  accessed_at: string | null;  // RFC 3339 string; only as good as the filesystem's atime
+  is_corrupt: boolean | null;  // null when the file was never fully decoded/probed
+  detected_language: string | null;  // ISO 639-1, from PDF/document text, email bodies or OCR
+  quality_score: number | null;      // 0-100 for culling; compare within a media type
 };
 
 export type Video = {
@@ -29,43 +35,210 @@ export type Video = {
   duration_sec: number | null;
   fps: number | null;
   codec: string | null;
+  recorded_at: string | null;  // RFC 3339, from container creation_time
+  is_hdr: boolean;
+  pix_fmt: string | null;          // e.g. "yuv420p10le"
+  color_space: string | null;      // e.g. "bt2020nc"
+  color_transfer: string | null;   // e.g. "smpte2084"
+  color_primaries: string | null;  // e.g. "bt2020"
+  has_decode_errors: boolean;      // ffmpeg hit errors decoding the first seconds
+  phash: string | null;            // perceptual hash of the first keyframe; Thorough only
+  chapters: VideoChapter[];        // container chapters; title may be ""
+  subtitle_tracks: SubtitleTrack[];
+  is_slow_motion: boolean;         // fps > 60, or captured faster than it plays back
+  is_timelapse: boolean;           // captured slower than it plays back (Android capture-rate tag only)
+  is_360: boolean;                 // spherical side data, 2:1 frame or "360"/"panorama" filename
+};
+
+export type SubtitleTrack = {
+  language: string | null;         // ISO 639 tag, e.g. "eng"; null when untagged
+  codec: string | null;            // e.g. "subrip", "mov_text", "hdmv_pgs_subtitle"
+  is_forced: boolean;
+};
+
+export type VideoChapter = {
+  title: string;
+  start_sec: number;
+  end_sec: number;
 };
 
 export type PDF = {
   page_count: number | null;
   page0_width_pt: number | null;
   page0_height_pt: number | null;
+  all_page_dimensions: [number, number][]; // [width_pt, height_pt], first 100 pages
+  has_mixed_page_sizes: boolean;
+  outline: PdfOutlineEntry[];
+  ocr_text: string | null;           // first page, only with the `ocr` build feature
+  text_excerpt: string | null;       // text layer of the first 20 pages; "thorough" profile only
+  word_count: number | null;         // whole text layer (Thorough), else the OCR'd first page
+  char_count: number | null;         // non-whitespace characters, same source
+  fonts: PdfFont[];                  // every page's /Font resources, deduplicated
+  preview_dpi: number | null;        // effective DPI of the page-0 preview; null without one
+};
+
+export type PdfOutlineEntry = {
+  title: string;
+  children: PdfOutlineEntry[];
+};
+
+export type PdfFont = {
+  name: string;         // /BaseFont; an "ABCDEF+" prefix marks a subset
+  subtype: string;      // Type1, TrueType, Type0, Type3, ...
+  is_embedded: boolean; // false means the printer has to supply the font
 };
 
 export type Image = {
   width: number | null;
   height: number | null;
   exif_datetime: string | null;
+  camera_make: string | null;
+  camera_model: string | null;
+  lens_make: string | null;
+  lens_model: string | null;
+  software: string | null;           // EXIF Software: editing app, or camera firmware on originals
   phash: string | null;              // base64
-  dominant_colors: string[];         // e.g. ["#aabbcc", ...]
+  dominant_colors: DominantColor[];  // most common first; options.color_count of them
+  is_grayscale: boolean | null;      // null when the image was never fully decoded
+  has_alpha: boolean;                // the format carries an alpha channel (header only)
+  has_transparency: boolean | null;  // some pixel is actually see-through; null when never decoded
+  validation_error: "file_not_found" | "not_an_image" | "truncated_file" | "unsupported_format" | null;
+  actual_format: string | null;      // by content signature: "jpeg", "png", "webp", ...
+  extension_mismatch: boolean;       // extension names another format than actual_format
+  is_screenshot: boolean | null;
+  screenshot_reason: string | null;
+  ocr_text: string | null;           // only with the `ocr` build feature
+  // "thorough" profile only:
+  exif: Record<string, string>;      // every EXIF field, tag name -> readable value
+  histogram: Histogram | null;       // null when pixels weren't decoded (quick, skip_previews, offline without OCR)
+  barcodes: string[];                // "<symbology>:<data>", e.g. "QR-Code:https://..."
+  gps_lat: number | null;            // decimal degrees, south negative
+  gps_lon: number | null;            // decimal degrees, west negative
+  sharpness: number | null;          // Laplacian variance; lower is blurrier. Threshold per library
+  noise_level: number | null;        // 0 clean .. 1 very noisy; null with TAGGER_SKIP_QUALITY=1
+  is_360: boolean;                   // GPano XMP, "360" in Make/Software, 2:1 frame or filename
+  is_burst_member: boolean;          // only set by detectBursts
+  sequence_id: number | null;        // numbered shots seconds apart share one; only set by detectSequences
+  page_count: number | null;         // TIFF only; previews and pixel facts describe page 1
+  page_dimensions: [number, number][]; // TIFF only: [width, height] in pixels per page
+  dpi_x: number | null;              // EXIF/JFIF resolution in pixels per inch; null when not recorded
+  dpi_y: number | null;
+};
+
+export type DominantColor = {
+  value: string;   // "#aabbcc", "rgb(170, 187, 204)" or "hsl(210, 25%, 73%)" per options.color_format
+  percent: number; // approximate share of the image, 0-100; for sizing swatches
+};
+
+export type Histogram = {
+  r: number[];  // 64 bins each
+  g: number[];
+  b: number[];
+};
+
+// Root <svg> attributes as written (units kept, e.g. "210mm").
+export type SVGMeta = {
+  view_box: string | null;
+  width: string | null;
+  height: string | null;
+  title: string | null;
+  description: string | null;
+  element_count: number | null;  // all elements, a rough complexity measure
+};
+
+// docx / xlsx / pptx document properties; counts are null for the other two kinds.
+export type OfficeMeta = {
+  app_name: string | null;     // e.g. "Microsoft Office Word"
+  author: string | null;
+  title: string | null;
+  word_count: number | null;   // docx
+  slide_count: number | null;  // pptx
+  sheet_count: number | null;  // xlsx
+};
+
+export type EmailAttachment = {
+  name: string;   // "" when the part has no filename
+  mime: string;
+};
+
+// Headers of an .eml message; encoded words (=?UTF-8?B?...?=) are decoded.
+export type EmailMeta = {
+  subject: string | null;
+  from: string | null;           // e.g. "\"Jane Doe\" <jane@example.com>"
+  to: string[];
+  date: string | null;           // RFC3339 when parseable, else the raw header
+  has_attachments: boolean;
+  attachment_count: number;
+  attachments: EmailAttachment[];
+};
+
+export type TagWithConfidence = {
+  tag: string;
+  confidence: number;    // 0..1; 1.0 when the source gave no score
 };
 
 export type Tagging = {
-  tags: string[];        // e.g. ["image","landscape","16:9"]
-  topics: string[];      // e.g. ["photography","hi_res_media"]
+  tags: TagWithConfidence[];    // e.g. [{tag:"landscape",confidence:0.9}, ...]
+  topics: TagWithConfidence[];  // e.g. [{tag:"photography",confidence:0.8}, ...]
   raw_keywords: string[]; 
+  unstemmed_keywords: string[];  // raw_keywords before stemming (TAGGER_KEYWORD_STEMMING); [] when off
+  ai_enriched: boolean;          // false: tags derived locally (offline, quick, no endpoint) or the AI call failed
+  ai_endpoint: string | null;    // which TAGGER_ENDPOINT entry answered (credentials stripped)
 };
 
 export type Suggested = { 
   rename: string 
   reason: string 
   confidence: number 
+  folder: string         // relative, e.g. "Invoices/2024"; "" when none
+  folder_reason: string
+  alternatives: Suggested[]  // lower-confidence suggestions, best first; [] when only one
 };
 
 export type MediaAnalysis = {
+  schema_version: number;  // 1; older cached results are upgraded on load
   meta: Metadata;
   video: Video;
   pdf: PDF;
   image: Image;
+  svg: SVGMeta;
+  office: OfficeMeta;
+  email: EmailMeta;
   tagging: Tagging;
   suggested: Suggested;
+  warnings: string[];
+  previews: MediaPreviews | null;  // only with AnalyseOptions.return_previews
+  error: string | null;            // analyseMedia / analyseMediaPaged only: why this file failed; other fields empty
 };
 
+export type MediaPreviews = {
+  image_b64: string | null;
+  image_mime: "image/jpeg" | "image/png" | "image/webp" | null;
+  video_frames_b64: string[] | null;
+  video_frames_mime: "image/jpeg" | "image/png" | "image/webp" | null;
+  pdf_page0_b64: string | null;
+  pdf_page0_mime: "image/jpeg" | "image/png" | "image/webp" | null;
+};
+
+export type AnalyseOptions = {
+  max_decode_bytes?: number;
+  offline?: boolean;  // skip the AI entirely; tags come from local keywords
+  max_external_processes?: number;  // concurrent ffprobe/ffmpeg/pdftoppm per job (default 4)
+  return_previews?: boolean;
+  profile?: AnalysisProfile;  // default "standard"
+  preview_format?: "auto" | "png" | "jpeg" | "webp";  // default TAGGER_PREVIEW_FORMAT or auto: PNG for transparency and PDF pages, else JPEG
+  preview_quality?: number;  // JPEG quality 1-100 (default 80); WebP is lossless
+  skip_previews?: boolean;   // metadata-only first pass: no previews, no AI (local tags)
+  instructions?: string;     // tagging style for the AI, e.g. "single-word tags only"
+  redact_location?: boolean; // don't send GPS coordinates to the AI (still returned locally)
+  color_count?: number;      // dominant colours per image (default 5; 0 for none)
+  color_format?: "hex" | "rgb" | "hsl";  // default "hex"
+};
+
+// quick: filename + dimensions, no external tools or AI. thorough: adds pHash, barcodes,
+// all EXIF fields, PDF text and larger AI previews.
+export type AnalysisProfile = "quick" | "standard" | "thorough";
+
 export type SortKey = "name" | "hue" | "date" | "tags" | "dimension";
 
 export type SubscriptionTier = "free" | "starter" | "pro" | "teams" | "enterprise";
@@ -98,6 +271,103 @@ export type UserState = {
 export type MergeNoOverlap<A, B> =
   [Extract<keyof A, keyof B>] extends [never] ? A & B : never;
 
+export type RenamePlan = {
+  old_path: string;
+  new_path: string;
+  collision: boolean;  // another entry, or an existing file, already has new_path
+};
+
+export type RenameOutcome = {
+  old_path: string;
+  new_path: string;
+  ok: boolean;
+  error: string | null;  // "rolled back" for entries undone after another failed
+};
+
+export type PrivacyReport = {
+  path: string;
+  has_gps: boolean;
+  has_face_hint: boolean;    // from AI tags/topics such as "person" or "portrait"
+  recommendations: string[]; // e.g. "Strip GPS location before sharing"
+};
+
+export type TagCount = { tag: string; count: number };
+
+export type BatchSummary = {
+  file_count: number;
+  counts_by_type: Record<string, number>; // "image" | "video" | "pdf" | ... -> files
+  total_bytes: number;
+  total_video_duration_sec: number;
+  top_tags: TagCount[];   // up to 20, most common first; counted once per file
+  top_topics: TagCount[];
+  ai_enriched: number;
+  degraded: number;       // file_count - ai_enriched
+};
+
+export type BurstSequence = {
+  members: number[]; // indices into the analyses passed to detectBursts, in capture order
+  best: number;      // sharpest member
+};
+
+export type BurstReport = {
+  analyses: MediaAnalysis[]; // as passed in, with image.is_burst_member set
+  bursts: BurstSequence[];
+};
+
+export type SimilarMatch = {
+  index: number;    // into the analyses passed to findSimilar
+  distance: number; // differing phash bits, 0-64; 0 = same picture
+};
+
+export type TagWriteOutcome = {
+  path: string;
+  written: boolean;
+  skipped_reason: string | null; // e.g. unsupported format; the file is left unchanged
+};
+
+export type HashAlgorithm = "sha256" | "md5";
+
+export type IntegrityCheck = {
+  matches: boolean;
+  actual_hash: string;    // lowercase hex
+  expected_hash: string;  // as given, trimmed and lowercased
+};
+
+export type FileAnalysedEvent = {
+  name: string;
+  path: string;
+  analysis: MediaAnalysis;
+};
+
+// One page of analyseMediaPaged; results are in the order of the files passed in.
+export type AnalysisPage = {
+  page_index: number;
+  total_pages: number;
+  results: MediaAnalysis[];
+};
+
+export type AnalysisResultEvent = {
+  job_id: number;
+  index: number;                   // position in the files passed to analyseMediaStreamed
+  path: string;
+  analysis: MediaAnalysis | null;  // null when this file failed; see error
+  error: string | null;
+};
+
+export type AnalysisDoneEvent = {
+  job_id: number;
+  total: number;
+  failed: number;
+  elapsed_ms: number;
+  cancelled: boolean; // stopped by cancelAnalysis; skipped files count as failed
+};
+
+export type AnalysisSession = {
+  schema_version: number;
+  exported_at: string | null;  // RFC 3339 string
+  files: { name: string; path: string; analysis: MediaAnalysis }[];
+};
+
 export type AnalysedFile = MergeNoOverlap<MediaAnalysis, LoadedFile>;
 
 export function merge<A extends object, B extends object>(
@@ -1,81 +1,247 @@
 //! analyse.rs — Local numeric metadata + real media previews to AI for tags/topics/rename.
 
 use serde::{Deserialize, Serialize};
-use std::{fs, path::Path, process::Command};
+use std::{fs, path::{Path, PathBuf}, process::Command};
+use std::collections::HashMap;
 use std::io::{Cursor, Read};
-use std::time::SystemTime;
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 
 use mime_guess::MimeGuess;
 use once_cell::sync::Lazy;
 use regex::Regex;
 
 use image::GenericImageView; // for .dimensions()
+use crate::job;
+use crate::migrate::ANALYSIS_SCHEMA_VERSION;
 use crate::types::*;
+use crate::util::sanitize_filename;
 
 // -----------------------------------------------------------------------------
 // Public API
 // -----------------------------------------------------------------------------
 
-pub fn analyse_single(file: LoadedFile) -> Result<MediaAnalysis, tauri::Error> {
+pub fn analyse_single(file: LoadedFile, opts: &AnalyseOptions) -> Result<MediaAnalysis, tauri::Error> {
+    let _limit = ProcessLimitScope::enter(opts.max_external_processes);
+
     // ---- Basic metadata
     let path = Path::new(&file.path);
     let mime = MimeGuess::from_path(path).first_raw().map(|s| s.to_string());
 
-    let (size_bytes, created_at, modified_at) = fs::metadata(path)
-        .ok()
-        .map(|md| {
-            (
-                Some(md.len()),
-                sys_time_to_rfc3339(md.created().ok()),
-                sys_time_to_rfc3339(md.modified().ok()),
-            )
-        })
-        .unwrap_or((None, None, None));
+    // Before anything opens the file: reading it may bump the access time.
+    let md = fs::metadata(path).ok();
 
     // ---- Init output
     let mut out = MediaAnalysis::default();
     out.meta.mime = mime.clone();
-    out.meta.size_bytes = size_bytes;
-    out.meta.created_at = created_at;
-    out.meta.modified_at = modified_at;
+    if let Some(md) = &md {
+        out.meta.size_bytes = Some(md.len());
+        let times = get_file_times(path, md);
+        out.meta.created_at = sys_time_to_rfc3339(times.created);
+        out.meta.modified_at = sys_time_to_rfc3339(times.modified);
+        out.meta.accessed_at = sys_time_to_rfc3339(times.accessed);
+        if is_stale(md) {
+            out.tagging.raw_keywords.push("unused".to_string());
+        }
+    }
 
     // ---- File type
     let ftype = get_type(&file.name);
-    out.meta.file_type = match ftype {
-        FileType::Pdf => "pdf",
-        FileType::Image => "image",
-        FileType::Video => "video",
-        FileType::Other => "other",
-    }.to_string();
-
-    // ---- Local numeric enrichment
+    out.meta.file_type = meta_file_type(&ftype);
+
+    let oversized = apply_size_guard(&file.name, &ftype, opts, &mut out);
+    let quick = opts.profile == AnalysisProfile::Quick;
+    let thorough = opts.profile == AnalysisProfile::Thorough;
+
+    // ---- Local numeric enrichment (Quick: headers only)
     match ftype {
         FileType::Image => {
+            apply_image_validation(validate_image_file(&file.path), &mut out);
+            if let Some(head) = read_head(&file.path, FORMAT_SNIFF_BYTES) {
+                detect_actual_format(&file.name, &head, &mut out);
+                jfif_dpi(&head, &mut out);
+            }
+            enrich_image_dims(&file.path, &mut out); // header only
+            if !oversized {
+                if let Ok(f) = fs::File::open(&file.path) { enrich_tiff_pages(std::io::BufReader::new(f), &mut out); }
+            }
+            if !oversized && !quick {
+                enrich_image_exif_keywords(&file.path, thorough, &mut out);
+                enrich_image_embedded_keywords(&file.path, &mut out);
+            }
+            if !oversized && thorough {
+                scan_barcodes(&file.path, &mut out);
+            }
+            detect_screenshot(&file.name, &mut out);
+        }
+        // RAW containers are TIFF-based (CR3/RAF aside), so rexif reads their EXIF as is.
+        FileType::RawImage if !oversized => {
             enrich_image_dims(&file.path, &mut out);
-            enrich_image_exif_keywords(&file.path, &mut out);
+            if !quick {
+                enrich_image_exif_keywords(&file.path, thorough, &mut out);
+                enrich_image_embedded_keywords(&file.path, &mut out);
+            }
+        }
+        FileType::Svg if !oversized => {
+            if let Err(e) = enrich_svg(&file.path, &mut out) {
+                log::warn!("[analyse {}] svg parse failed: {e}", job::current());
+                out.warnings.push(format!("svg metadata unavailable: {e}"));
+            }
+        }
+        FileType::Office if !quick => {
+            if let Err(e) = enrich_office(&file.path, &mut out) {
+                log::warn!("[analyse {}] office parse failed: {e}", job::current());
+                out.warnings.push(format!("document metadata unavailable: {e}"));
+            }
+        }
+        FileType::Email if !oversized => {
+            if let Err(e) = enrich_email(&file.path, &mut out) {
+                log::warn!("[analyse {}] email parse failed: {e}", job::current());
+                out.warnings.push(format!("email headers unavailable: {e}"));
+            }
         }
-        FileType::Video => {
+        FileType::Video if !quick => {
             if let Err(e) = enrich_video_ffprobe(&file.path, &mut out) {
-                eprintln!("[analyse] ffprobe failed: {e}");
+                log::warn!("[analyse {}] ffprobe failed: {e}", job::current());
+                out.warnings.push(format!("video metadata unavailable: {e}"));
             }
         }
-        FileType::Pdf => {
-            if let Err(e) = enrich_pdf_lopdf(&file.path, &mut out) {
-                eprintln!("[analyse] pdf parse failed: {e}");
+        FileType::Pdf if !quick => {
+            if let Err(e) = enrich_pdf_lopdf(&file.path, thorough, &mut out) {
+                log::warn!("[analyse {}] pdf parse failed: {e}", job::current());
+                out.warnings.push(format!("pdf metadata unavailable: {e}"));
             }
         }
-        FileType::Other => {}
+        _ => {}
+    }
+    if matches!(ftype, FileType::Image | FileType::RawImage | FileType::Video) {
+        detect_360_media(&file.name, &mut out);
     }
-
-    // ---- Seed raw keywords from filename/EXIF
-    let mut raw_keywords = gather_keywords(&file.name);
-    // (EXIF push already done in enrich_image_exif_keywords)
 
     // ---- Build real-media previews for AI
-    let previews = prepare_media_previews(&file, mime.as_deref())?;
+    let previews = if oversized || (opts.offline && !OCR_ENABLED) || quick || opts.skip_previews {
+        MediaPreviews::default()
+    } else {
+        prepare_media_previews(&file, mime.as_deref(), opts, &mut out)?
+    };
+
+    Ok(finish_with_ai(&file.name, out, previews, opts))
+}
+
+/// Same as [`analyse_single`] but for an in-memory buffer (e.g. a file received over the network).
+/// Images are decoded straight from `data`; videos and PDFs go through a tempfile because
+/// ffprobe/lopdf/pdftoppm need a path. The tempfile is removed before returning.
+pub fn analyse_single_bytes(name: &str, data: &[u8], opts: &AnalyseOptions) -> Result<MediaAnalysis, tauri::Error> {
+    let _limit = ProcessLimitScope::enter(opts.max_external_processes);
+    let ftype = get_type(name);
+    if !matches!(ftype, FileType::Image) {
+        return analyse_via_tempfile(name, data, opts);
+    }
+
+    let mut out = MediaAnalysis::default();
+    out.meta.mime = MimeGuess::from_path(name).first_raw().map(|s| s.to_string());
+    out.meta.size_bytes = Some(data.len() as u64);
+    out.meta.file_type = meta_file_type(&ftype);
+
+    let oversized = apply_size_guard(name, &ftype, opts, &mut out);
+    apply_image_validation(validate_image_bytes(data), &mut out);
+    detect_actual_format(name, data, &mut out);
+    jfif_dpi(data, &mut out);
+
+    if let Ok((w, h)) = image::ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(image::ImageError::IoError)
+        .and_then(|r| r.into_dimensions())
+    {
+        out.image.width = Some(w);
+        out.image.height = Some(h);
+        out.image.has_alpha = has_alpha_channel(image::ImageReader::new(Cursor::new(data)));
+    } else if let Some((w, h)) = heif_dimensions(&data[..data.len().min(HEIF_HEAD_BYTES as usize)]) {
+        out.image.width = Some(w);
+        out.image.height = Some(h);
+    }
+    if !oversized { enrich_tiff_pages(Cursor::new(data), &mut out); }
+    let quick = opts.profile == AnalysisProfile::Quick;
+    if !oversized && !quick {
+        if let Ok(exif) = rexif::parse_buffer(data) {
+            apply_exif_entries(exif, opts.profile == AnalysisProfile::Thorough, &mut out);
+        }
+        apply_embedded_keywords(data, &mut out);
+    }
+    // No barcode scan here: zbarimg needs a path.
+    detect_screenshot(name, &mut out);
+    detect_360_media(name, &mut out);
+
+    let mut previews = MediaPreviews::default();
+    if !oversized && !(opts.offline && !OCR_ENABLED) && !quick && !opts.skip_previews {
+        if let Some(img) = decode_checked(image::load_from_memory(data), &mut out) {
+            enrich_image_pixels(&img, opts, &mut out);
+            let (b64, mime) = downscale_image_b64(img, preview_sizes(opts.profile).image_max_side, PreviewEncoding::from(opts))?;
+            previews.image_b64 = Some(b64);
+            previews.image_mime = Some(mime.to_string());
+        }
+    }
+
+    Ok(finish_with_ai(name, out, previews, opts))
+}
+
+fn analyse_via_tempfile(name: &str, data: &[u8], opts: &AnalyseOptions) -> Result<MediaAnalysis, tauri::Error> {
+    use std::io::Write;
+
+    // Keep the extension: ffmpeg's demuxer probing and MimeGuess both use it.
+    let suffix = Path::new(name).extension().and_then(|e| e.to_str()).map(|e| format!(".{e}")).unwrap_or_default();
+    let mut tmp = tempfile::Builder::new()
+        .suffix(&suffix)
+        .tempfile_in(&*TEMP_DIR)
+        .map_err(|e| ioerr(format!("tempfile: {e}")))?;
+    tmp.write_all(data).map_err(|e| ioerr(format!("tempfile write: {e}")))?;
+    tmp.flush().map_err(|e| ioerr(format!("tempfile flush: {e}")))?;
+
+    let loaded = LoadedFile { name: name.to_string(), path: tmp.path().to_string_lossy().to_string() };
+    let mut out = analyse_single(loaded, opts)?;
+    // Filesystem times describe the tempfile, not the media.
+    out.meta.created_at = None;
+    out.meta.modified_at = None;
+    out.meta.accessed_at = None;
+    Ok(out)
+}
+
+/// Seeds keywords, runs the AI call and folds its answer into `out`.
+fn finish_with_ai(name: &str, mut out: MediaAnalysis, previews: MediaPreviews, opts: &AnalyseOptions) -> MediaAnalysis {
+    // ---- Seed raw keywords from filename, then keep what enrichment found (EXIF, PDF outline)
+    let mut raw_keywords = gather_keywords(name);
+    for k in std::mem::take(&mut out.tagging.raw_keywords) {
+        if !raw_keywords.contains(&k) { raw_keywords.push(k); }
+    }
+
+    // ---- OCR on the previews (no-op unless built with the `ocr` feature); local, so offline too
+    if opts.profile != AnalysisProfile::Quick {
+        enrich_ocr(&mut out, &previews);
+        if out.meta.detected_language.is_none() {
+            if let Some(text) = out.image.ocr_text.clone().or_else(|| out.pdf.ocr_text.clone()) {
+                detect_language(&text, &mut out);
+            }
+        }
+    }
+
+    // ---- Offline / Quick / skip_previews: never touch the network, derive semantics locally
+    if opts.offline || opts.profile == AnalysisProfile::Quick || opts.skip_previews {
+        let (tags, topics) = offline_tags(&out, &raw_keywords);
+        out.tagging.tags = tags.into_iter().map(TagWithConfidence::from).collect();
+        out.tagging.topics = topics.into_iter().map(TagWithConfidence::from).collect();
+        set_raw_keywords(&mut out, raw_keywords);
+        out.schema_version = ANALYSIS_SCHEMA_VERSION;
+        return out; // offline, previews are only built for OCR
+    }
 
     // ---- AI call: only for semantic fields
-    if let Some(ai) = maybe_ai_enrichment(&file, &out, &raw_keywords, &previews) {
+    let ai = maybe_ai_enrichment(name, &out, &raw_keywords, &previews, opts);
+    if ai.is_none() && job::current_cancelled() {
+        out.warnings.push("AI tagging skipped: analysis was cancelled".to_string());
+    }
+    if let Some(ai) = ai {
+        out.tagging.ai_enriched = true;
+        out.tagging.ai_endpoint = ai.served_by;
         if let Some(tags) = ai.tags { out.tagging.tags = tags; }
         if let Some(topics) = ai.topics { out.tagging.topics = topics; }
         if let Some(extra_kw) = ai.raw_keywords {
@@ -85,27 +251,76 @@ pub fn analyse_single(file: LoadedFile) -> Result<MediaAnalysis, tauri::Error> {
                 }
             }
         }
-        if let Some(s) = ai.suggested {
-            if !s.rename.is_empty() {
-                out.suggested = s;
-            }
+        if let Some(s) = ranked_suggestion(ai.suggested, ai.suggested_alternatives) {
+            out.suggested = s;
         }
     }
 
-    out.tagging.raw_keywords = raw_keywords;
-    Ok(out)
+    set_raw_keywords(&mut out, raw_keywords);
+    out.schema_version = ANALYSIS_SCHEMA_VERSION;
+    if opts.return_previews {
+        out.previews = Some(previews);
+    }
+    out
 }
 
+/// Suggested folders are joined onto a user-chosen root, so they must stay inside it:
+/// relative, no `..`, no drive or UNC prefix.
+fn is_safe_relative_folder(folder: &str) -> bool {
+    use std::path::Component;
+    if folder.is_empty() { return true; }
+    let unified = folder.replace('\\', "/");
+    if unified.starts_with('/') || unified.contains(':') { return false; }
+    Path::new(&unified).components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
 
-fn get_type(file_name: &str) -> FileType {
-    match file_name.rsplit('.').next() {
-        Some(ext) => match ext.to_lowercase().as_str() {
-            "pdf" => FileType::Pdf,
-            "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp" => FileType::Image,
-            "mp4" | "mov" | "avi" | "mkv" | "webm" => FileType::Video,
-            _ => FileType::Other,
-        },
-        None => FileType::Other,
+fn meta_file_type(ftype: &FileType) -> MetaFileType {
+    match ftype {
+        FileType::Pdf => MetaFileType::Pdf,
+        FileType::Image | FileType::RawImage | FileType::Svg => MetaFileType::Image,
+        FileType::Video => MetaFileType::Video,
+        FileType::Office => MetaFileType::Office,
+        FileType::Email => MetaFileType::Email,
+        FileType::Other => MetaFileType::Other,
+    }
+}
+
+/// Size guard: anything we'd slurp into memory is skipped when oversized.
+/// Returns true when the caller should stick to metadata only.
+fn apply_size_guard(name: &str, ftype: &FileType, opts: &AnalyseOptions, out: &mut MediaAnalysis) -> bool {
+    let decodable = matches!(
+        ftype,
+        FileType::Image | FileType::RawImage | FileType::Svg | FileType::Email | FileType::Other
+    );
+    let size_bytes = out.meta.size_bytes;
+    let oversized = decodable && size_bytes.map_or(false, |n| n > opts.max_decode_bytes);
+    if oversized {
+        let msg = format!(
+            "file is {} bytes (limit {}); skipping decode",
+            size_bytes.unwrap_or(0), opts.max_decode_bytes
+        );
+        log::info!("[analyse {}] {name}: {msg}", job::current());
+        out.warnings.push(msg);
+    }
+    oversized
+}
+
+
+pub(crate) fn get_type(file_name: &str) -> FileType {
+    // Path::extension gives None for `README` and `.hidden`, and `gz` for `a.tar.gz`.
+    let ext = match Path::new(file_name).extension().and_then(|e| e.to_str()) {
+        Some(ext) => ext.to_lowercase(),
+        None => return FileType::Other,
+    };
+    match ext.as_str() {
+        "pdf" => FileType::Pdf,
+        "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp" | "tif" | "tiff" | "avif" => FileType::Image,
+        "cr2" | "cr3" | "nef" | "arw" | "dng" | "orf" | "rw2" | "raf" => FileType::RawImage,
+        "svg" => FileType::Svg,
+        "docx" | "xlsx" | "pptx" => FileType::Office,
+        "eml" => FileType::Email,
+        "mp4" | "mov" | "avi" | "mkv" | "webm" => FileType::Video,
+        _ => FileType::Other,
     }
 }
 
@@ -113,11 +328,344 @@ fn get_type(file_name: &str) -> FileType {
 // Common helpers
 // -----------------------------------------------------------------------------
 
-fn sys_time_to_rfc3339(ts: Option<SystemTime>) -> Option<String> {
+pub(crate) fn sys_time_to_rfc3339(ts: Option<SystemTime>) -> Option<String> {
     use time::{format_description::well_known::Rfc3339, OffsetDateTime};
     ts.and_then(|t| OffsetDateTime::from(t).format(&Rfc3339).ok())
 }
 
+struct FileTimes {
+    created: Option<SystemTime>,
+    modified: Option<SystemTime>,
+    accessed: Option<SystemTime>,
+}
+
+/// Timestamps for `path`, whose metadata the caller already has. `created` is `None` where
+/// the platform or filesystem doesn't record a birth time.
+fn get_file_times(path: &Path, md: &fs::Metadata) -> FileTimes {
+    FileTimes {
+        created: md.created().ok().or_else(|| birth_time(path)),
+        modified: md.modified().ok(),
+        accessed: md.accessed().ok(),
+    }
+}
+
+/// `stx_btime` straight from statx(2), for when std couldn't report it (older kernels,
+/// some libc builds). Filesystems without birth times (ext3, many network mounts) leave
+/// BTIME out of the returned mask.
+#[cfg(target_os = "linux")]
+fn birth_time(path: &Path) -> Option<SystemTime> {
+    use rustix::fs::{statx, AtFlags, StatxFlags, CWD};
+    let st = statx(CWD, path, AtFlags::empty(), StatxFlags::BTIME).ok()?;
+    if st.stx_mask & StatxFlags::BTIME.bits() == 0 {
+        return None;
+    }
+    let secs = u64::try_from(st.stx_btime.tv_sec).ok()?; // pre-1970 birth times are noise
+    SystemTime::UNIX_EPOCH.checked_add(Duration::new(secs, st.stx_btime.tv_nsec))
+}
+
+/// macOS and Windows always record a birth time, so `Metadata::created` is all there is.
+#[cfg(not(target_os = "linux"))]
+fn birth_time(_path: &Path) -> Option<SystemTime> {
+    None
+}
+
+// Days the last access may trail the last modification before a file counts as unused,
+// from TAGGER_STALE_AFTER_DAYS (default 365).
+static STALE_AFTER: Lazy<Duration> = Lazy::new(|| {
+    let days = match std::env::var("TAGGER_STALE_AFTER_DAYS") {
+        Err(_) => 365,
+        Ok(raw) => raw.trim().parse::<u64>().unwrap_or_else(|_| {
+            log::warn!("[analyse] ignoring TAGGER_STALE_AFTER_DAYS={raw:?}: expected whole days");
+            365
+        }),
+    };
+    Duration::from_secs(days * 24 * 60 * 60)
+});
+
+/// Not read since long before its last modification. Only meaningful where the filesystem
+/// tracks access times (noatime mounts and some Windows setups never update them).
+fn is_stale(md: &fs::Metadata) -> bool {
+    match (md.accessed(), md.modified()) {
+        (Ok(accessed), Ok(modified)) => modified.duration_since(accessed).is_ok_and(|d| d > *STALE_AFTER),
+        _ => false,
+    }
+}
+
+/// Locates an external tool (ffprobe/ffmpeg/pdftoppm/tesseract): `<NAME>_PATH` (e.g.
+/// `FFPROBE_PATH`, for binaries bundled with the app) first, then PATH.
+fn find_tool(name: &str) -> Option<PathBuf> {
+    let var = format!("{}_PATH", name.to_ascii_uppercase());
+    if let Some(p) = std::env::var_os(&var).map(PathBuf::from) {
+        if p.is_file() { return Some(p); }
+        warn_tool_once(name, &format!("{var}={} does not exist, falling back to PATH", p.display()));
+    }
+    let found = which::which(name).ok();
+    if found.is_none() {
+        warn_tool_once(name, &format!("{name} not found (set {var} or add it to PATH)"));
+    }
+    found
+}
+
+// One warning per tool per run; the lookups happen for every file.
+static TOOL_WARNINGS: Lazy<Mutex<std::collections::HashSet<String>>> = Lazy::new(Default::default);
+
+fn warn_tool_once(name: &str, msg: &str) {
+    if TOOL_WARNINGS.lock().unwrap().insert(format!("{name}:{msg}")) {
+        log::warn!("[analyse] {msg}");
+    }
+}
+
+// -----------------------------------------------------------------------------
+// External process gate
+// -----------------------------------------------------------------------------
+
+// Caps concurrent ffprobe/ffmpeg/pdftoppm runs per job, so file-level parallelism can't
+// spawn dozens of transcoders at once. Per job (not process-wide) so a batch and the
+// watcher, or two batches with different `max_external_processes`, don't overwrite each
+// other's limit. Image decoding is deliberately not gated.
+static PROCESS_GATE: Lazy<(Mutex<HashMap<Option<u64>, usize>>, Condvar)> =
+    Lazy::new(|| (Mutex::new(HashMap::new()), Condvar::new()));
+
+const DEFAULT_PROCESS_LIMIT: usize = 4;
+
+thread_local! {
+    static PROCESS_LIMIT: std::cell::Cell<usize> = const { std::cell::Cell::new(DEFAULT_PROCESS_LIMIT) };
+}
+
+/// Applies `AnalyseOptions::max_external_processes` to the permits this thread acquires,
+/// until dropped. Every file of a job carries the same options, so the job's limit holds.
+struct ProcessLimitScope(usize);
+
+impl ProcessLimitScope {
+    fn enter(limit: usize) -> Self {
+        ProcessLimitScope(PROCESS_LIMIT.with(|l| l.replace(limit.max(1))))
+    }
+}
+
+impl Drop for ProcessLimitScope {
+    fn drop(&mut self) {
+        PROCESS_LIMIT.with(|l| l.set(self.0));
+    }
+}
+
+/// Held while a tool runs; counted against the job entered on this thread.
+struct ProcessPermit(Option<u64>);
+
+impl ProcessPermit {
+    fn acquire() -> Self {
+        let (job, limit) = (job::current_id(), PROCESS_LIMIT.with(std::cell::Cell::get));
+        let (lock, cvar) = &*PROCESS_GATE;
+        let mut in_use = lock.lock().unwrap_or_else(|e| e.into_inner());
+        while in_use.get(&job).is_some_and(|&n| n >= limit) {
+            in_use = cvar.wait(in_use).unwrap_or_else(|e| e.into_inner());
+        }
+        *in_use.entry(job).or_default() += 1;
+        ProcessPermit(job)
+    }
+}
+
+impl Drop for ProcessPermit {
+    fn drop(&mut self) {
+        let (lock, cvar) = &*PROCESS_GATE;
+        let mut in_use = lock.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(n) = in_use.get_mut(&self.0) {
+            *n -= 1;
+            if *n == 0 { in_use.remove(&self.0); }
+        }
+        // Waiters of every job share the condvar; wake them all so the right one gets in.
+        cvar.notify_all();
+    }
+}
+
+// How long one external tool run (ffprobe, ffmpeg, pdftoppm, exiftool, ...) may take, from
+// TAGGER_TOOL_TIMEOUT_SECS, else the older TAGGER_FFPROBE_TIMEOUT_SECS (default 30). A file
+// on a stalled network share or a named pipe would otherwise hold its permit forever.
+static TOOL_TIMEOUT: Lazy<Duration> = Lazy::new(|| {
+    let var = ["TAGGER_TOOL_TIMEOUT_SECS", "TAGGER_FFPROBE_TIMEOUT_SECS"]
+        .into_iter()
+        .find_map(|name| std::env::var(name).ok().map(|raw| (name, raw)));
+    let secs = match var {
+        None => 30,
+        Some((name, raw)) => match raw.trim().parse::<u64>() {
+            Ok(s) if s > 0 => s,
+            _ => {
+                log::warn!("[analyse] ignoring {name}={raw:?}: expected whole seconds > 0");
+                30
+            }
+        },
+    };
+    Duration::from_secs(secs)
+});
+
+/// Failures the pipeline reports as a kind rather than a message.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AnalyseError {
+    /// ffprobe or ffmpeg could not read the video in time.
+    #[error("video probe failed: {0}")]
+    VideoProbeError(String),
+}
+
+impl From<AnalyseError> for tauri::Error {
+    fn from(e: AnalyseError) -> Self {
+        ioerr(e.to_string())
+    }
+}
+
+/// A timed-out ffprobe/ffmpeg run becomes [`AnalyseError::VideoProbeError`]; other failures
+/// pass through.
+fn video_tool_error(e: std::io::Error) -> anyhow::Error {
+    if e.kind() == std::io::ErrorKind::TimedOut {
+        AnalyseError::VideoProbeError(e.to_string()).into()
+    } else {
+        e.into()
+    }
+}
+
+const PROCESS_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// `Command::output` with a deadline: the child is killed once `TOOL_TIMEOUT` passes and
+/// a `TimedOut` error names `tool`. stdout/stderr are drained on threads so a chatty child
+/// can't block on a full pipe while we wait.
+fn output_with_timeout(cmd: &mut Command, tool: &str) -> std::io::Result<std::process::Output> {
+    output_within(cmd, tool, *TOOL_TIMEOUT)
+}
+
+fn output_within(cmd: &mut Command, tool: &str, timeout: Duration) -> std::io::Result<std::process::Output> {
+    use std::process::Stdio;
+    let mut child = cmd.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let drain = |pipe: Option<Box<dyn Read + Send>>| {
+        std::thread::spawn(move || {
+            let mut buf = vec![];
+            if let Some(mut p) = pipe { let _ = p.read_to_end(&mut buf); }
+            buf
+        })
+    };
+    let stdout = drain(child.stdout.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+    let stderr = drain(child.stderr.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? { break status; }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(std::io::Error::new(std::io::ErrorKind::TimedOut, format!("{tool} timeout")));
+        }
+        std::thread::sleep(PROCESS_POLL_INTERVAL);
+    };
+    Ok(std::process::Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+// -----------------------------------------------------------------------------
+// Scratch space for external tools (ffmpeg frames, pdftoppm/SVG rasters)
+// -----------------------------------------------------------------------------
+
+// Where tools write their scratch files, from TAGGER_TEMP_DIR (default: the OS temp
+// directory), for machines whose system temp sits on a small volume. Must already exist.
+static TEMP_DIR: Lazy<PathBuf> = Lazy::new(|| {
+    let Some(dir) = std::env::var_os("TAGGER_TEMP_DIR").filter(|d| !d.is_empty()).map(PathBuf::from) else {
+        return std::env::temp_dir();
+    };
+    if !dir.is_dir() {
+        log::warn!("[analyse] TAGGER_TEMP_DIR={} is not a directory; previews that need scratch space will be skipped", dir.display());
+    }
+    dir
+});
+
+// Free space the temp directory must have before we ask a tool to write into it, from
+// TAGGER_MIN_TEMP_SPACE_MB (default 256). On a full disk ffmpeg and pdftoppm just exit
+// non-zero, which is indistinguishable from a broken file.
+static MIN_TEMP_SPACE_BYTES: Lazy<u64> = Lazy::new(|| {
+    let mb = std::env::var("TAGGER_MIN_TEMP_SPACE_MB").ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(256);
+    mb * 1024 * 1024
+});
+
+/// Why the temp directory can't take tool output right now; None when there is room (or
+/// the free space can't be determined, in which case the tool gets to try).
+fn temp_space_shortfall() -> Option<String> {
+    let dir = &*TEMP_DIR;
+    if !dir.is_dir() {
+        return Some(format!("temp directory {} does not exist (TAGGER_TEMP_DIR)", dir.display()));
+    }
+    let free = fs2::available_space(dir).ok()?;
+    (free < *MIN_TEMP_SPACE_BYTES).then(|| format!(
+        "low disk space: {} MB free in {}, need {} MB (TAGGER_MIN_TEMP_SPACE_MB)",
+        free / (1024 * 1024),
+        dir.display(),
+        *MIN_TEMP_SPACE_BYTES / (1024 * 1024),
+    ))
+}
+
+const SCRATCH_REMOVE_ATTEMPTS: u32 = 3;
+
+/// Temp directory removed on drop, whichever way the function holding it returns. Removal
+/// is retried briefly: on Windows a tool that just exited can hold its output open for a
+/// moment, and `TempDir`'s own drop gives up silently.
+struct ScratchDir(Option<tempfile::TempDir>);
+
+impl ScratchDir {
+    fn new() -> Result<Self, tauri::Error> {
+        tempfile::Builder::new()
+            .tempdir_in(&*TEMP_DIR)
+            .map(|d| ScratchDir(Some(d)))
+            .map_err(|e| ioerr(format!("tempdir in {}: {e}", TEMP_DIR.display())))
+    }
+
+    fn path(&self) -> &Path {
+        self.0.as_ref().expect("scratch dir already removed").path()
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let Some(dir) = self.0.take() else { return; };
+        let path = dir.path().to_path_buf();
+        if dir.close().is_ok() { return; }
+        for attempt in 1..SCRATCH_REMOVE_ATTEMPTS {
+            std::thread::sleep(Duration::from_millis(50 * attempt as u64));
+            match fs::remove_dir_all(&path) {
+                Ok(()) => return,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+                Err(_) => {}
+            }
+        }
+        log::warn!("[analyse {}] could not remove temp dir {}", job::current(), path.display());
+    }
+}
+
+static DATETIME_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(\d{4})[-:](\d{2})[-:](\d{2})[T ](\d{2}):(\d{2}):(\d{2})(?:\.\d+)?\s*(Z|[+-]\d{2}:?\d{2})?$").unwrap()
+});
+
+/// Normalizes the timestamp shapes ffprobe/EXIF emit (`2023-05-01T12:34:56.000000Z`,
+/// `2023-05-01 12:34:56`, `2023-05-01T12:34:56+0200`, `2023:05:01 12:34:56`) to RFC3339.
+/// Missing offsets are taken as UTC. Zeroed container dates (1904/1970 epochs) are rejected.
+pub(crate) fn normalize_datetime(s: &str) -> Option<String> {
+    use time::{format_description::well_known::Rfc3339, Date, Month, PrimitiveDateTime, Time, UtcOffset};
+    let c = DATETIME_RE.captures(s.trim())?;
+    let n = |i: usize| c.get(i).and_then(|m| m.as_str().parse::<i32>().ok());
+    let year = n(1)?;
+    if year <= 1970 { return None; }
+    let date = Date::from_calendar_date(year, Month::try_from(n(2)? as u8).ok()?, n(3)? as u8).ok()?;
+    let time = Time::from_hms(n(4)? as u8, n(5)? as u8, n(6)? as u8).ok()?;
+    let offset = match c.get(7).map(|m| m.as_str()) {
+        None | Some("Z") => UtcOffset::UTC,
+        Some(off) => {
+            let digits = off[1..].replace(':', "");
+            let (h, m) = (digits[..2].parse::<i8>().ok()?, digits[2..].parse::<i8>().ok()?);
+            let sign = if off.starts_with('-') { -1 } else { 1 };
+            UtcOffset::from_hms(sign * h, sign * m, 0).ok()?
+        }
+    };
+    PrimitiveDateTime::new(date, time).assume_offset(offset).format(&Rfc3339).ok()
+}
+
 static SPLIT_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"[^A-Za-z0-9]+").unwrap());
 static STOP: Lazy<Vec<&'static str>> = Lazy::new(|| {
     vec!["the","a","an","and","or","of","to","in","on","for","with","by",
@@ -125,11 +673,126 @@ static STOP: Lazy<Vec<&'static str>> = Lazy::new(|| {
          "photo","image","video","movie","clip","scan","page","pg","doc"]
 });
 
+// Keyword length bounds, read once from TAGGER_MIN_KEYWORD_LEN / TAGGER_MAX_KEYWORD_LEN.
+// Long tokens are usually hashes or random ids, so the max is opt-in.
+static MIN_KEYWORD_LEN: Lazy<usize> =
+    Lazy::new(|| keyword_len_env("TAGGER_MIN_KEYWORD_LEN").unwrap_or(3));
+static MAX_KEYWORD_LEN: Lazy<Option<usize>> =
+    Lazy::new(|| keyword_len_env("TAGGER_MAX_KEYWORD_LEN"));
+
+fn keyword_len_env(var: &str) -> Option<usize> {
+    let raw = std::env::var(var).ok()?;
+    match raw.trim().parse::<usize>() {
+        Ok(n) if n >= 1 => Some(n),
+        _ => {
+            log::warn!("[analyse] ignoring {var}={raw:?}: expected an integer >= 1");
+            None
+        }
+    }
+}
+
+/// EXIF string fields that can feed `raw_keywords`, by rexif `ExifTag` name, with their tag
+/// number. Matching on the number also covers tags rexif doesn't know (Artist, the serials).
+const EXIF_STRING_TAGS: &[(&str, u16)] = &[
+    ("ImageDescription", 0x010e), ("Make", 0x010f), ("Model", 0x0110), ("Software", 0x0131),
+    ("DateTime", 0x0132), ("Artist", 0x013b), ("HostComputer", 0x013c), ("Copyright", 0x8298),
+    ("DateTimeOriginal", 0x9003), ("DateTimeDigitized", 0x9004), ("ImageUniqueID", 0xa420),
+    ("CameraOwnerName", 0xa430), ("BodySerialNumber", 0xa431), ("LensMake", 0xa433),
+    ("LensModel", 0xa434), ("LensSerialNumber", 0xa435),
+];
+
+// Which EXIF fields become raw keywords, from TAGGER_EXIF_KEYWORD_TAGS (e.g. "Artist,Copyright").
+// Default: camera and lens make/model, and Software (tells edited photos from originals).
+static EXIF_KEYWORD_TAGS: Lazy<Vec<u16>> = Lazy::new(|| {
+    let Ok(raw) = std::env::var("TAGGER_EXIF_KEYWORD_TAGS") else {
+        return vec![0x010f, 0x0110, 0xa433, 0xa434, 0x0131];
+    };
+    raw.split(',')
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .filter_map(|name| {
+            let found = EXIF_STRING_TAGS.iter().find(|(n, _)| n.eq_ignore_ascii_case(name));
+            if found.is_none() {
+                log::warn!("[analyse] TAGGER_EXIF_KEYWORD_TAGS: ignoring unknown EXIF tag {name:?}");
+            }
+            found.map(|&(_, tag)| tag)
+        })
+        .collect()
+});
+
+// Snowball stemmer for raw keywords, from TAGGER_KEYWORD_STEMMING: "1"/"true"/"porter" for
+// English, or a language name ("german", "french", ...). Off by default.
+static KEYWORD_STEMMER: Lazy<Option<rust_stemmers::Stemmer>> = Lazy::new(|| {
+    use rust_stemmers::Algorithm;
+    let raw = std::env::var("TAGGER_KEYWORD_STEMMING").ok()?;
+    let algorithm = match raw.trim().to_ascii_lowercase().as_str() {
+        "" | "0" | "false" | "off" => return None,
+        "1" | "true" | "on" | "porter" | "english" => Algorithm::English,
+        "danish" => Algorithm::Danish,
+        "dutch" => Algorithm::Dutch,
+        "finnish" => Algorithm::Finnish,
+        "french" => Algorithm::French,
+        "german" => Algorithm::German,
+        "italian" => Algorithm::Italian,
+        "norwegian" => Algorithm::Norwegian,
+        "portuguese" => Algorithm::Portuguese,
+        "russian" => Algorithm::Russian,
+        "spanish" => Algorithm::Spanish,
+        "swedish" => Algorithm::Swedish,
+        _ => {
+            log::warn!("[analyse] ignoring TAGGER_KEYWORD_STEMMING={raw:?}: unknown language");
+            return None;
+        }
+    };
+    Some(rust_stemmers::Stemmer::create(algorithm))
+});
+
+/// Reads every `TAGGER_*` setting this module uses (keywords, tool timeout, temp dir, AI
+/// endpoints, ...) up front, so bad values are reported once at startup rather than on
+/// the first file. Also binds PDFium, so its library is looked up once. Call after the logger
+/// is installed.
+pub fn load_env_config() {
+    Lazy::force(&MIN_KEYWORD_LEN);
+    Lazy::force(&MAX_KEYWORD_LEN);
+    Lazy::force(&EXIF_KEYWORD_TAGS);
+    Lazy::force(&KEYWORD_STEMMER);
+    Lazy::force(&SCREEN_RESOLUTIONS_ALL);
+    Lazy::force(&SKIP_QUALITY);
+    Lazy::force(&TOOL_TIMEOUT);
+    Lazy::force(&STALE_AFTER);
+    Lazy::force(&TEMP_DIR);
+    Lazy::force(&SMART_CROP);
+    Lazy::force(&AI_ENDPOINTS);
+    Lazy::force(&PDFIUM);
+}
+
+/// Stores the final keyword list. With stemming on, "photos" and "photo" collapse into one
+/// stem (first occurrence keeps its place) and the words as found go to `unstemmed_keywords`.
+/// Runs last, so the AI and the offline tagger still see whole words.
+fn set_raw_keywords(out: &mut MediaAnalysis, keywords: Vec<String>) {
+    let Some(stemmer) = KEYWORD_STEMMER.as_ref() else {
+        out.tagging.raw_keywords = keywords;
+        return;
+    };
+    let mut stems: Vec<String> = Vec::with_capacity(keywords.len());
+    for k in &keywords {
+        // Multi-word keywords (EXIF makes, sender names) are stemmed word by word.
+        let stem = k.split(' ').map(|w| stemmer.stem(w)).collect::<Vec<_>>().join(" ");
+        if !stems.contains(&stem) { stems.push(stem); }
+    }
+    out.tagging.raw_keywords = stems;
+    out.tagging.unstemmed_keywords = keywords;
+}
+
+fn keyword_len_ok(w: &str) -> bool {
+    w.len() >= *MIN_KEYWORD_LEN && MAX_KEYWORD_LEN.map_or(true, |max| w.len() <= max)
+}
+
 fn gather_keywords(name: &str) -> Vec<String> {
     let mut v: Vec<String> = SPLIT_RE.split(name)
         .filter_map(|w| {
             let w = w.trim().to_lowercase();
-            if w.len() >= 3 && !STOP.contains(&w.as_str()) { Some(w) } else { None }
+            if keyword_len_ok(&w) && !STOP.contains(&w.as_str()) { Some(w) } else { None }
         })
         .collect();
     v.sort();
@@ -140,7 +803,7 @@ fn gather_keywords(name: &str) -> Vec<String> {
 fn maybe_push_kw(keywords: &mut Vec<String>, s: &str) {
     for w in SPLIT_RE.split(s) {
         let w = w.trim().to_lowercase();
-        if w.len() >= 3 && !STOP.contains(&w.as_str()) {
+        if keyword_len_ok(&w) && !STOP.contains(&w.as_str()) {
             if !keywords.iter().any(|k| k == &w) { keywords.push(w); }
         }
     }
@@ -150,138 +813,1806 @@ fn maybe_push_kw(keywords: &mut Vec<String>, s: &str) {
 // Image numeric
 // -----------------------------------------------------------------------------
 
+/// Why an image can't be used, as found by [`validate_image_file`] from the header and
+/// trailer alone (no full decode).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageValidationError {
+    FileNotFound,
+    NotAnImage,
+    TruncatedFile,
+    UnsupportedFormat,
+}
+
+impl ImageValidationError {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImageValidationError::FileNotFound => "file_not_found",
+            ImageValidationError::NotAnImage => "not_an_image",
+            ImageValidationError::TruncatedFile => "truncated_file",
+            ImageValidationError::UnsupportedFormat => "unsupported_format",
+        }
+    }
+
+    fn message(&self) -> &'static str {
+        match self {
+            ImageValidationError::FileNotFound => "file not found",
+            ImageValidationError::NotAnImage => "file content is not an image",
+            ImageValidationError::TruncatedFile => "image file is truncated (incomplete download or copy?)",
+            ImageValidationError::UnsupportedFormat => "image format is not supported",
+        }
+    }
+}
+
+// How far from the end to look for the JPEG/PNG end marker; trailing junk is common.
+const IMAGE_TRAILER_WINDOW: u64 = 4096;
+
+pub fn validate_image_file(path: &str) -> Option<ImageValidationError> {
+    use std::io::{Seek, SeekFrom};
+    let mut f = match fs::File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Some(ImageValidationError::FileNotFound),
+        Err(_) => return None, // unreadable for other reasons: not ours to classify
+    };
+    let len = f.metadata().ok()?.len();
+    let mut head = Vec::new();
+    (&mut f).take(16).read_to_end(&mut head).ok()?;
+    let mut tail = Vec::new();
+    f.seek(SeekFrom::Start(len.saturating_sub(IMAGE_TRAILER_WINDOW))).ok()?;
+    f.read_to_end(&mut tail).ok()?;
+
+    let dims = image::ImageReader::open(path)
+        .and_then(|r| r.with_guessed_format())
+        .map_err(image::ImageError::IoError)
+        .and_then(|r| r.into_dimensions());
+    classify_image(&head, &tail, dims)
+}
+
+fn validate_image_bytes(data: &[u8]) -> Option<ImageValidationError> {
+    let tail = &data[data.len().saturating_sub(IMAGE_TRAILER_WINDOW as usize)..];
+    let dims = image::ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(image::ImageError::IoError)
+        .and_then(|r| r.into_dimensions());
+    classify_image(&data[..data.len().min(16)], tail, dims)
+}
+
+fn classify_image(head: &[u8], tail: &[u8], dims: image::ImageResult<(u32, u32)>) -> Option<ImageValidationError> {
+    use image::{ImageError, ImageFormat};
+    if head.is_empty() { return Some(ImageValidationError::TruncatedFile); }
+    let Ok(format) = image::guess_format(head) else { return Some(ImageValidationError::NotAnImage); };
+    if !format.reading_enabled() { return Some(ImageValidationError::UnsupportedFormat); }
+    match dims {
+        Err(ImageError::Unsupported(_)) => return Some(ImageValidationError::UnsupportedFormat),
+        Err(_) => return Some(ImageValidationError::TruncatedFile), // magic ok, header cut short or garbled
+        Ok(_) => {}
+    }
+    // A cut-off body leaves the header intact; the end marker is what goes missing.
+    let has = |needle: &[u8]| tail.windows(needle.len()).any(|w| w == needle);
+    let complete = match format {
+        ImageFormat::Jpeg => has(&[0xFF, 0xD9]),
+        ImageFormat::Png => has(b"IEND"),
+        _ => true,
+    };
+    (!complete).then_some(ImageValidationError::TruncatedFile)
+}
+
+/// Records a validation failure on the analysis; truncation also marks the file corrupt.
+fn apply_image_validation(err: Option<ImageValidationError>, out: &mut MediaAnalysis) {
+    let Some(err) = err else { return; };
+    out.image.validation_error = Some(err.as_str().to_string());
+    out.warnings.push(err.message().to_string());
+    if err == ImageValidationError::TruncatedFile {
+        out.meta.is_corrupt = Some(true);
+    }
+}
+
+/// Enough for every signature `image::guess_format` knows.
+const FORMAT_SNIFF_BYTES: u64 = 32;
+
+/// The format the content actually is (by signature, as the decoder picks it), and whether
+/// the extension claims another one. Extensions `image` doesn't know (HEIC, RAW) never count
+/// as a mismatch.
+fn detect_actual_format(name: &str, head: &[u8], out: &mut MediaAnalysis) {
+    let Ok(actual) = image::guess_format(head) else { return };
+    let actual_name = format!("{actual:?}").to_lowercase();
+    let ext = Path::new(name).extension().and_then(|e| e.to_str()).unwrap_or_default();
+    if image::ImageFormat::from_extension(ext).is_some_and(|claimed| claimed != actual) {
+        out.image.extension_mismatch = true;
+        out.warnings.push(format!("file extension .{ext} does not match its content ({actual_name})"));
+    }
+    out.image.actual_format = Some(actual_name);
+}
+
+/// Pixel density from a JPEG's JFIF header (APP0 right after SOI), which must fit in the
+/// sniffed head. Units 0 only give an aspect ratio; EXIF, read later, wins when present.
+fn jfif_dpi(head: &[u8], out: &mut MediaAnalysis) {
+    if head.len() < 18 || head[..4] != [0xff, 0xd8, 0xff, 0xe0] || &head[6..11] != b"JFIF\0" { return; }
+    let per_cm = match head[13] {
+        1 => false,
+        2 => true,
+        _ => return,
+    };
+    let density = |i: usize| f64::from(u16::from_be_bytes([head[i], head[i + 1]]));
+    set_dpi(density(14), density(16), per_cm, out);
+}
+
+/// Zero densities mean "not recorded".
+fn set_dpi(x: f64, y: f64, per_cm: bool, out: &mut MediaAnalysis) {
+    let factor = if per_cm { 2.54 } else { 1.0 };
+    if x > 0.0 && y > 0.0 && x.is_finite() && y.is_finite() {
+        out.image.dpi_x = Some(x * factor);
+        out.image.dpi_y = Some(y * factor);
+    }
+}
+
 fn enrich_image_dims(path: &str, out: &mut MediaAnalysis) {
     if let Ok((w, h)) = image::image_dimensions(path) {
         out.image.width = Some(w);
         out.image.height = Some(h);
+        if let Ok(reader) = image::ImageReader::open(path) {
+            out.image.has_alpha = has_alpha_channel(reader);
+        }
+    } else if let Some((w, h)) = read_head(path, HEIF_HEAD_BYTES).and_then(|head| heif_dimensions(&head)) {
+        out.image.width = Some(w);
+        out.image.height = Some(h);
+    } else if matches!(get_type(path), FileType::RawImage) {
+        enrich_raw_dims(path, out);
     }
 }
 
-fn enrich_image_exif_keywords(path: &str, out: &mut MediaAnalysis) {
-    if let Ok(exif) = rexif::parse_file(path) {
-        for entry in exif.entries {
-            use rexif::ExifTag;
-            match entry.tag {
-                ExifTag::DateTimeOriginal => {
-                    let dt_str = entry.value_more_readable.trim().to_string();
-                    if !dt_str.is_empty() { out.image.exif_datetime = Some(dt_str); }
-                }
-                ExifTag::Make | ExifTag::Model => {
-                    let s = entry.value_more_readable.to_lowercase();
-                    if !s.is_empty() && !out.tagging.raw_keywords.iter().any(|k| k == &s) {
-                        out.tagging.raw_keywords.push(s);
-                    }
-                }
-                _ => {}
+const TIFF_MAX_PAGES: usize = 1000;
+
+/// Page count and per-page pixel sizes of a (multi-page) TIFF: scans and faxes. Walks the
+/// IFD chain only; the preview is the first page, which is all `image` decodes. No-op for
+/// other formats.
+fn enrich_tiff_pages<R: Read + std::io::Seek>(reader: R, out: &mut MediaAnalysis) {
+    let Ok(mut dec) = tiff::decoder::Decoder::new(reader) else { return };
+    let mut pages = vec![];
+    loop {
+        match dec.dimensions() {
+            Ok(dims) => pages.push(dims),
+            Err(e) => {
+                log::warn!("[analyse {}] tiff page {} unreadable: {e}", job::current(), pages.len() + 1);
+                break;
             }
         }
+        if !dec.more_images() || pages.len() >= TIFF_MAX_PAGES { break; }
+        if let Err(e) = dec.next_image() {
+            log::warn!("[analyse {}] tiff page {} unreadable: {e}", job::current(), pages.len() + 1);
+            break;
+        }
     }
+    if pages.is_empty() { return; }
+    out.image.page_count = Some(pages.len() as u32);
+    out.image.page_dimensions = pages;
 }
 
-// -----------------------------------------------------------------------------
-// Video numeric (ffprobe)
-// -----------------------------------------------------------------------------
+/// The boxes before the image data (ftyp, meta) are small; `ispe` sits well within this.
+const HEIF_HEAD_BYTES: u64 = 64 * 1024;
 
-#[derive(Deserialize)]
-struct FfStream { codec_type: Option<String>, codec_name: Option<String>, width: Option<u32>, height: Option<u32>, avg_frame_rate: Option<String> }
-#[derive(Deserialize)]
-struct FfFormat { duration: Option<String> }
-#[derive(Deserialize)]
-struct FfProbe { streams: Option<Vec<FfStream>>, format: Option<FfFormat> }
+fn read_head(path: &str, len: u64) -> Option<Vec<u8>> {
+    let mut head = Vec::new();
+    fs::File::open(path).ok()?.take(len).read_to_end(&mut head).ok()?;
+    Some(head)
+}
 
-fn enrich_video_ffprobe(path: &str, out: &mut MediaAnalysis) -> anyhow::Result<()> {
-    let ff = which::which("ffprobe").map_err(|_| anyhow::anyhow!("ffprobe not found"))?;
-    let output = Command::new(ff)
-        .args(["-v","quiet","-print_format","json","-show_format","-show_streams",path])
-        .output()?;
-    if !output.status.success() { return Err(anyhow::anyhow!("ffprobe failed")); }
-    let parsed: FfProbe = serde_json::from_slice(&output.stdout)?;
+/// Size from the first `ispe` (image spatial extents) property of an HEIF-family file
+/// such as AVIF, which `image` can only read when built with the `avif` feature. Encoders
+/// write the primary item's properties first.
+fn heif_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    if data.get(4..8) != Some(b"ftyp") { return None; }
+    // box type, then version/flags, width, height
+    let i = data.windows(4).position(|w| w == b"ispe")?;
+    let u32_at = |o: usize| data.get(o..o + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+    let (w, h) = (u32_at(i + 8)?, u32_at(i + 12)?);
+    (w > 0 && h > 0).then_some((w, h))
+}
 
-    if let Some(fmt) = parsed.format {
-        if let Some(d) = fmt.duration { if let Ok(secs) = d.parse::<f64>() { out.video.duration_sec = Some(secs); } }
+/// From the decoder's colour type, so only the header is read.
+fn has_alpha_channel<R: std::io::BufRead + std::io::Seek>(reader: image::ImageReader<R>) -> bool {
+    use image::ImageDecoder;
+    reader.with_guessed_format().ok()
+        .and_then(|r| r.into_decoder().ok())
+        .is_some_and(|d| d.color_type().has_alpha())
+}
+
+/// Sensor size minus the maker's crop, as displayed (portrait shots swapped). rawloader has
+/// no header-only mode, so this decodes the raw data; callers respect the size guard.
+fn enrich_raw_dims(path: &str, out: &mut MediaAnalysis) {
+    use rawloader::Orientation;
+    match rawloader::decode_file(path) {
+        Ok(raw) => {
+            let [top, right, bottom, left] = raw.crops;
+            let w = raw.width.saturating_sub(left + right) as u32;
+            let h = raw.height.saturating_sub(top + bottom) as u32;
+            let portrait = matches!(
+                raw.orientation,
+                Orientation::Rotate90 | Orientation::Rotate270 | Orientation::Transpose | Orientation::Transverse
+            );
+            let (w, h) = if portrait { (h, w) } else { (w, h) };
+            out.image.width = Some(w);
+            out.image.height = Some(h);
+        }
+        Err(e) => {
+            log::warn!("[analyse {}] raw decode failed for {path}: {e}", job::current());
+            out.warnings.push(format!("raw dimensions unavailable: {e}"));
+        }
     }
-    if let Some(streams) = parsed.streams {
-        if let Some(vs) = streams.iter().find(|s| s.codec_type.as_deref() == Some("video")) {
-            out.video.codec  = vs.codec_name.clone();
-            out.video.width  = vs.width;
-            out.video.height = vs.height;
-            if let Some(r) = &vs.avg_frame_rate { if let Some(fps) = parse_rational(r) { out.video.fps = Some(fps); } }
+}
+
+/// The JPEG most RAW files embed for the camera's own display, via exiftool. The largest
+/// candidates come first; the AI preview gets downscaled anyway.
+fn raw_embedded_preview(path: &str) -> Option<image::DynamicImage> {
+    let exiftool = find_tool("exiftool")?;
+    for tag in ["-JpgFromRaw", "-PreviewImage", "-ThumbnailImage"] {
+        let _permit = ProcessPermit::acquire();
+        let output = match output_with_timeout(Command::new(&exiftool).args(["-b", tag, path]), "exiftool") {
+            Ok(o) => o,
+            Err(e) => {
+                log::warn!("[analyse {}] exiftool failed for {path}: {e}", job::current());
+                return None;
+            }
+        };
+        if !output.status.success() || output.stdout.is_empty() { continue; }
+        if let Ok(img) = image::load_from_memory(&output.stdout) {
+            return Some(img);
         }
     }
-    Ok(())
+    None
 }
-fn parse_rational(s: &str) -> Option<f64> {
-    let mut it = s.split('/');
-    let a = it.next()?.parse::<f64>().ok()?;
-    let b = it.next()?.parse::<f64>().ok()?;
+
+fn enrich_image_exif_keywords(path: &str, all_fields: bool, out: &mut MediaAnalysis) {
+    if let Ok(exif) = rexif::parse_file(path) {
+        apply_exif_entries(exif, all_fields, out);
+    }
+}
+
+const EXIF_VALUE_MAX_CHARS: usize = 200;
+
+/// Picks out the fields we use; with `all_fields`, also copies every entry into `image.exif`
+/// (tag name -> readable value; maker-note blobs and the like are cut at 200 chars).
+fn apply_exif_entries(exif: rexif::ExifData, all_fields: bool, out: &mut MediaAnalysis) {
+    // GPS values and their N/S, E/W refs are separate entries, in no particular order.
+    let (mut lat, mut lon, mut lat_ref, mut lon_ref) = (None, None, None, None);
+    // Likewise the resolution and its unit; EXIF's default unit is inches.
+    let (mut x_res, mut y_res, mut res_unit) = (None, None, 2u16);
+    for entry in exif.entries {
+        use rexif::ExifTag;
+        if all_fields {
+            let name = match entry.tag {
+                ExifTag::UnknownToMe => format!("Tag 0x{:04x}", entry.ifd.tag),
+                tag => tag.to_string(),
+            };
+            let value: String = entry.value_more_readable.trim().chars().take(EXIF_VALUE_MAX_CHARS).collect();
+            if !value.is_empty() { out.image.exif.insert(name, value); }
+        }
+        match entry.tag {
+            ExifTag::DateTimeOriginal => {
+                let dt_str = entry.value_more_readable.trim().to_string();
+                if !dt_str.is_empty() { out.image.exif_datetime = Some(dt_str); }
+            }
+            ExifTag::Make | ExifTag::Model => {
+                let raw = entry.value_more_readable.trim().to_string();
+                if mentions_360(&raw) { out.image.is_360 = true; }
+                if !raw.is_empty() {
+                    if entry.tag == ExifTag::Make { out.image.camera_make = Some(raw); }
+                    else { out.image.camera_model = Some(raw); }
+                }
+            }
+            ExifTag::Software => {
+                let raw = entry.value_more_readable.trim().to_string();
+                if mentions_360(&raw) { out.image.is_360 = true; }
+                if !raw.is_empty() { out.image.software = Some(raw); }
+            }
+            ExifTag::LensMake | ExifTag::LensModel => {
+                let raw = entry.value_more_readable.trim().to_string();
+                if !raw.is_empty() {
+                    if entry.tag == ExifTag::LensMake { out.image.lens_make = Some(raw); }
+                    else { out.image.lens_model = Some(raw); }
+                }
+            }
+            ExifTag::GPSLatitude => lat = gps_degrees(&entry.value),
+            ExifTag::GPSLongitude => lon = gps_degrees(&entry.value),
+            ExifTag::GPSLatitudeRef => lat_ref = gps_ref(&entry.value),
+            ExifTag::GPSLongitudeRef => lon_ref = gps_ref(&entry.value),
+            // The first of each: a JPEG thumbnail's IFD repeats them.
+            ExifTag::XResolution if x_res.is_none() => x_res = first_rational(&entry.value),
+            ExifTag::YResolution if y_res.is_none() => y_res = first_rational(&entry.value),
+            ExifTag::ResolutionUnit => {
+                if let rexif::TagValue::U16(v) = &entry.value { res_unit = v.first().copied().unwrap_or(2); }
+            }
+            _ => {}
+        }
+        if EXIF_KEYWORD_TAGS.contains(&entry.ifd.tag) {
+            // Tags unknown to rexif have no readable value; take the raw string.
+            let s = match &entry.value {
+                rexif::TagValue::Ascii(s) => s.trim().to_lowercase(),
+                _ => entry.value_more_readable.trim().to_lowercase(),
+            };
+            if !s.is_empty() && !out.tagging.raw_keywords.iter().any(|k| k == &s) {
+                out.tagging.raw_keywords.push(s);
+            }
+        }
+    }
+    // Cameras without a fix often write 0/0 rationals; those come out as NaN and are dropped.
+    let signed = |deg: f64, r: Option<char>, neg: char| if r == Some(neg) { -deg } else { deg };
+    if let (Some(la), Some(lo)) = (lat, lon) {
+        let (la, lo) = (signed(la, lat_ref, 'S'), signed(lo, lon_ref, 'W'));
+        if la.is_finite() && lo.is_finite() && la.abs() <= 90.0 && lo.abs() <= 180.0 {
+            out.image.gps_lat = Some(la);
+            out.image.gps_lon = Some(lo);
+        }
+    }
+    // EXIF units: 1 none, 2 inch, 3 centimetre.
+    if let (Some(x), Some(y), 2 | 3) = (x_res, y_res, res_unit) {
+        set_dpi(x, y, res_unit == 3, out);
+    }
+}
+
+fn first_rational(value: &rexif::TagValue) -> Option<f64> {
+    let rexif::TagValue::URational(r) = value else { return None; };
+    r.first().map(|r| r.value())
+}
+
+/// Degrees/minutes/seconds rationals to decimal degrees.
+fn gps_degrees(value: &rexif::TagValue) -> Option<f64> {
+    let rexif::TagValue::URational(dms) = value else { return None; };
+    let part = |i: usize| dms.get(i).map_or(0.0, |r| r.value());
+    (!dms.is_empty()).then(|| part(0) + part(1) / 60.0 + part(2) / 3600.0)
+}
+
+fn gps_ref(value: &rexif::TagValue) -> Option<char> {
+    let rexif::TagValue::Ascii(s) = value else { return None; };
+    s.trim().chars().next().map(|c| c.to_ascii_uppercase())
+}
+
+// -----------------------------------------------------------------------------
+// SVG (XML only; nothing is rendered here)
+// -----------------------------------------------------------------------------
+
+/// Root attributes, the root's own <title>/<desc>, and the element count as a rough
+/// complexity measure. Streams the file, so large SVGs aren't held in memory.
+fn enrich_svg(path: &str, out: &mut MediaAnalysis) -> anyhow::Result<()> {
+    use quick_xml::events::Event;
+    let mut reader = quick_xml::Reader::from_file(path)?;
+    reader.config_mut().trim_text(true);
+
+    let mut buf = vec![];
+    let mut depth = 0usize;
+    let mut count = 0u32;
+    // Which root-level text element we're inside, if any.
+    let mut capture: Option<bool> = None; // Some(true) = title, Some(false) = desc
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) => {
+                count += 1;
+                depth += 1;
+                if depth == 1 {
+                    read_svg_root(&e, out)?;
+                } else if depth == 2 {
+                    capture = match e.local_name().as_ref() {
+                        b"title" if out.svg.title.is_none() => Some(true),
+                        b"desc" if out.svg.description.is_none() => Some(false),
+                        _ => None,
+                    };
+                }
+            }
+            Event::Empty(e) => {
+                count += 1;
+                if depth == 0 { read_svg_root(&e, out)?; }
+            }
+            Event::End(_) => {
+                depth = depth.saturating_sub(1);
+                capture = None;
+            }
+            Event::Text(t) if capture.is_some() => {
+                let text = t.unescape()?.trim().to_string();
+                let slot = if capture == Some(true) { &mut out.svg.title } else { &mut out.svg.description };
+                if !text.is_empty() { *slot = Some(text); }
+            }
+            Event::CData(t) if capture.is_some() => {
+                let text = String::from_utf8_lossy(&t).trim().to_string();
+                let slot = if capture == Some(true) { &mut out.svg.title } else { &mut out.svg.description };
+                if !text.is_empty() { *slot = Some(text); }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    out.svg.element_count = Some(count);
+    Ok(())
+}
+
+fn read_svg_root(e: &quick_xml::events::BytesStart, out: &mut MediaAnalysis) -> anyhow::Result<()> {
+    if e.local_name().as_ref() != b"svg" {
+        anyhow::bail!("root element is not <svg>");
+    }
+    let attr = |name: &str| -> Option<String> {
+        let v = e.try_get_attribute(name).ok()??.unescape_value().ok()?.trim().to_string();
+        (!v.is_empty()).then_some(v)
+    };
+    out.svg.view_box = attr("viewBox");
+    out.svg.width = attr("width");
+    out.svg.height = attr("height");
+    Ok(())
+}
+
+/// Renders with rsvg-convert, else Inkscape, fitting into `max_side`. None when neither is
+/// installed or the render fails.
+fn rasterize_svg(path: &str, max_side: u32) -> Result<Option<image::DynamicImage>, tauri::Error> {
+    let tmpdir = ScratchDir::new()?;
+    let png = tmpdir.path().join("svg.png");
+    let png_arg = png.to_string_lossy().to_string();
+    let side = max_side.to_string();
+
+    let output = if let Some(rsvg) = find_tool("rsvg-convert") {
+        let _permit = ProcessPermit::acquire();
+        output_with_timeout(
+            Command::new(rsvg).args(["-w", &side, "-h", &side, "--keep-aspect-ratio", "-o", &png_arg, path]),
+            "rsvg-convert",
+        )
+    } else if let Some(inkscape) = find_tool("inkscape") {
+        let _permit = ProcessPermit::acquire();
+        output_with_timeout(
+            Command::new(inkscape).args(["--export-type=png", &format!("--export-filename={png_arg}"), "-w", &side, path]),
+            "inkscape",
+        )
+    } else {
+        return Ok(None);
+    };
+    match output {
+        Ok(o) if o.status.success() => Ok(image::open(&png).ok()),
+        Ok(_) => Ok(None),
+        Err(e) => {
+            log::warn!("[analyse {}] svg rasterize failed for {path}: {e}", job::current());
+            Ok(None)
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Office Open XML (docx / xlsx / pptx): ZIP of XML parts, nothing is rendered
+// -----------------------------------------------------------------------------
+
+/// Parts are streamed; this only guards against absurd (or zip-bomb) entries.
+const OFFICE_XML_MAX_BYTES: u64 = 256 * 1024 * 1024;
+
+fn enrich_office(path: &str, out: &mut MediaAnalysis) -> anyhow::Result<()> {
+    use quick_xml::events::Event;
+    let mut zip = zip::ZipArchive::new(fs::File::open(path)?)?;
+
+    // Dublin Core: <dc:title>, <dc:creator>
+    let mut current: Vec<u8> = vec![];
+    read_zip_xml(&mut zip, "docProps/core.xml", |ev| match ev {
+        Event::Start(e) => current = e.local_name().as_ref().to_vec(),
+        Event::End(_) => current.clear(),
+        Event::Text(t) => {
+            let slot = match current.as_slice() {
+                b"title" => &mut out.office.title,
+                b"creator" => &mut out.office.author,
+                _ => return,
+            };
+            if let Ok(text) = t.unescape() {
+                let text = text.trim();
+                if !text.is_empty() { *slot = Some(text.to_string()); }
+            }
+        }
+        _ => {}
+    })?;
+
+    current.clear();
+    read_zip_xml(&mut zip, "docProps/app.xml", |ev| match ev {
+        Event::Start(e) => current = e.local_name().as_ref().to_vec(),
+        Event::End(_) => current.clear(),
+        Event::Text(t) if current == b"Application" => {
+            if let Ok(text) = t.unescape() {
+                let text = text.trim();
+                if !text.is_empty() { out.office.app_name = Some(text.to_string()); }
+            }
+        }
+        _ => {}
+    })?;
+
+    // Words are counted across runs: a word split over two <w:t> (formatting change
+    // mid-word) counts once; paragraph, tab and break boundaries end a word.
+    let (mut words, mut in_text, mut in_word) = (0u32, false, false);
+    let mut sample = String::new(); // for language detection
+    let is_word = read_zip_xml(&mut zip, "word/document.xml", |ev| match ev {
+        Event::Start(e) if e.local_name().as_ref() == b"t" => in_text = true,
+        Event::End(e) if e.local_name().as_ref() == b"t" => in_text = false,
+        Event::End(e) if e.local_name().as_ref() == b"p" => {
+            in_word = false;
+            sample.push('\n');
+        }
+        Event::Empty(e) if matches!(e.local_name().as_ref(), b"tab" | b"br" | b"cr") => in_word = false,
+        Event::Text(t) if in_text => {
+            let Ok(text) = t.unescape() else { return; };
+            if sample.len() < LANGUAGE_SAMPLE_CHARS { sample.push_str(&text); }
+            for c in text.chars() {
+                if c.is_whitespace() {
+                    in_word = false;
+                } else if !in_word {
+                    words += 1;
+                    in_word = true;
+                }
+            }
+        }
+        _ => {}
+    })?;
+    if is_word { out.office.word_count = Some(words); }
+
+    let mut sheets = 0u32;
+    if read_zip_xml(&mut zip, "xl/workbook.xml", |ev| {
+        if let Event::Start(e) | Event::Empty(e) = ev {
+            if e.local_name().as_ref() == b"sheet" { sheets += 1; }
+        }
+    })? {
+        out.office.sheet_count = Some(sheets);
+    }
+
+    let mut slides = 0u32;
+    if read_zip_xml(&mut zip, "ppt/presentation.xml", |ev| {
+        if let Event::Start(e) | Event::Empty(e) = ev {
+            if e.local_name().as_ref() == b"sldId" { slides += 1; }
+        }
+    })? {
+        out.office.slide_count = Some(slides);
+    }
+
+    // Spreadsheets keep their text in the shared string table, slides one part per slide.
+    for part in ["xl/sharedStrings.xml", "ppt/slides/slide1.xml", "ppt/slides/slide2.xml"] {
+        if sample.len() >= LANGUAGE_SAMPLE_CHARS { break; }
+        in_text = false;
+        read_zip_xml(&mut zip, part, |ev| match ev {
+            Event::Start(e) if e.local_name().as_ref() == b"t" => in_text = true,
+            Event::End(e) if e.local_name().as_ref() == b"t" => {
+                in_text = false;
+                sample.push('\n');
+            }
+            Event::Text(t) if in_text && sample.len() < LANGUAGE_SAMPLE_CHARS => {
+                if let Ok(text) = t.unescape() { sample.push_str(&text); }
+            }
+            _ => {}
+        })?;
+    }
+    detect_language(&sample, out);
+    Ok(())
+}
+
+/// Feeds every event of the part `name` to `on_event`; false when the part doesn't exist.
+fn read_zip_xml<R: Read + std::io::Seek>(
+    zip: &mut zip::ZipArchive<R>,
+    name: &str,
+    mut on_event: impl FnMut(&quick_xml::events::Event<'_>),
+) -> anyhow::Result<bool> {
+    use quick_xml::events::Event;
+    let entry = match zip.by_name(name) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(false),
+        Err(e) => return Err(e.into()),
+    };
+    let mut reader = quick_xml::Reader::from_reader(std::io::BufReader::new(entry.take(OFFICE_XML_MAX_BYTES)));
+    let mut buf = vec![];
+    loop {
+        let ev = reader.read_event_into(&mut buf)?;
+        if matches!(ev, Event::Eof) { break; }
+        on_event(&ev);
+        buf.clear();
+    }
+    Ok(true)
+}
+
+// -----------------------------------------------------------------------------
+// Email (.eml): RFC 822 headers and the MIME tree, attachments listed not decoded
+// -----------------------------------------------------------------------------
+
+static ENCODED_WORD_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"=\?([^?]+)\?([bBqQ])\?([^?]*)\?=").unwrap());
+static ENCODED_WORD_GAP_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\?=\s+=\?").unwrap());
+static MIME_PARAM_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i);\s*([a-z0-9*_-]+)\s*=\s*(?:"((?:[^"\\]|\\.)*)"|([^;\s]+))"#).unwrap());
+
+/// How deep nested multiparts (forwarded mail inside forwarded mail) are followed.
+const EMAIL_MAX_MIME_DEPTH: usize = 8;
+
+fn enrich_email(path: &str, out: &mut MediaAnalysis) -> anyhow::Result<()> {
+    let raw = fs::read(path)?;
+    let text = String::from_utf8_lossy(&raw).replace("\r\n", "\n");
+    let (head, body) = text.split_once("\n\n").unwrap_or((&text, ""));
+    let headers = parse_mail_headers(head);
+    if headers.is_empty() {
+        anyhow::bail!("no RFC 822 headers");
+    }
+
+    let e = &mut out.email;
+    e.subject = mail_header(&headers, "subject").map(decode_encoded_words).filter(|s| !s.is_empty());
+    e.from = mail_header(&headers, "from").map(decode_encoded_words).filter(|s| !s.is_empty());
+    e.to = mail_header(&headers, "to")
+        .map(|v| split_addresses(&decode_encoded_words(v)))
+        .unwrap_or_default();
+    e.date = mail_header(&headers, "date").map(|d| {
+        use time::format_description::well_known::{Rfc2822, Rfc3339};
+        time::OffsetDateTime::parse(d.trim(), &Rfc2822).ok()
+            .and_then(|dt| dt.format(&Rfc3339).ok())
+            .unwrap_or_else(|| d.trim().to_string())
+    });
+
+    let mut body_text = None;
+    walk_mime_parts(&headers, body, 0, &mut e.attachments, &mut body_text);
+    e.attachment_count = e.attachments.len() as u32;
+    e.has_attachments = e.attachment_count > 0;
+
+    let sample = format!("{}\n{}", e.subject.as_deref().unwrap_or(""), body_text.unwrap_or_default());
+
+    // Subject words like a filename; the sender whole, like camera make/model.
+    let mut keywords = e.subject.as_deref().map(gather_keywords).unwrap_or_default();
+    if let Some(from) = e.from.as_deref() {
+        let sender = from.split('<').next().unwrap_or(from).trim().trim_matches('"');
+        let sender = if sender.is_empty() { from.trim_matches(|c| c == '<' || c == '>') } else { sender };
+        keywords.push(sender.to_lowercase());
+    }
+    for k in keywords {
+        if !k.is_empty() && !out.tagging.raw_keywords.contains(&k) {
+            out.tagging.raw_keywords.push(k);
+        }
+    }
+    detect_language(&sample, out);
+    Ok(())
+}
+
+/// `(name, value)` pairs in order, with folded continuation lines joined.
+fn parse_mail_headers(head: &str) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = vec![];
+    for line in head.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, v)) = headers.last_mut() {
+                v.push(' ');
+                v.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+    }
+    headers
+}
+
+fn mail_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+}
+
+/// A parameter of a structured header (`boundary`, `filename`, `name`). RFC 2231's
+/// `filename*=utf-8''na%C3%AFve.pdf` form is percent-decoded.
+fn mime_param(value: &str, name: &str) -> Option<String> {
+    let mut plain = None;
+    for c in MIME_PARAM_RE.captures_iter(value) {
+        let key = c[1].to_ascii_lowercase();
+        let v = c.get(2).or(c.get(3)).map(|m| m.as_str()).unwrap_or("");
+        if key == name {
+            plain = Some(decode_encoded_words(&v.replace("\\\"", "\"")));
+        } else if key == format!("{name}*") {
+            let encoded = v.splitn(3, '\'').nth(2).unwrap_or(v);
+            return Some(percent_decode(encoded));
+        }
+    }
+    plain
+}
+
+fn percent_decode(s: &str) -> String {
+    String::from_utf8_lossy(&unescape_hex(s, b'%')).to_string()
+}
+
+/// Replaces `<esc>XX` hex escapes (`%XX` in URLs and RFC 2231, `=XX` in quoted-printable).
+fn unescape_hex(s: &str, esc: u8) -> Vec<u8> {
+    let b = s.as_bytes();
+    let mut bytes = Vec::with_capacity(b.len());
+    let mut i = 0;
+    while i < b.len() {
+        let hex = (b[i] == esc && i + 2 < b.len())
+            .then(|| std::str::from_utf8(&b[i + 1..i + 3]).ok().and_then(|h| u8::from_str_radix(h, 16).ok()))
+            .flatten();
+        match hex {
+            Some(v) => { bytes.push(v); i += 3; }
+            None => { bytes.push(b[i]); i += 1; }
+        }
+    }
+    bytes
+}
+
+/// RFC 2047 `=?charset?B|Q?...?=` words. UTF-8 and Latin-1 are decoded; other charsets
+/// are read as UTF-8, which is right for their ASCII part.
+fn decode_encoded_words(s: &str) -> String {
+    let s = ENCODED_WORD_GAP_RE.replace_all(s, "?==?");
+    ENCODED_WORD_RE.replace_all(&s, |c: &regex::Captures| {
+        let bytes = if c[2].eq_ignore_ascii_case("b") {
+            base64::decode(&c[3]).unwrap_or_default()
+        } else {
+            unescape_hex(&c[3].replace('_', " "), b'=')
+        };
+        match c[1].to_ascii_lowercase().as_str() {
+            "iso-8859-1" | "latin1" | "us-ascii" => bytes.iter().map(|&b| b as char).collect(),
+            _ => String::from_utf8_lossy(&bytes).to_string(),
+        }
+    }).trim().to_string()
+}
+
+/// Splits an address list on commas outside quotes and angle brackets.
+fn split_addresses(list: &str) -> Vec<String> {
+    let (mut out, mut cur) = (vec![], String::new());
+    let (mut quoted, mut angle) = (false, false);
+    for ch in list.chars() {
+        match ch {
+            '"' => quoted = !quoted,
+            '<' if !quoted => angle = true,
+            '>' if !quoted => angle = false,
+            ',' if !quoted && !angle => {
+                out.push(std::mem::take(&mut cur));
+                continue;
+            }
+            _ => {}
+        }
+        cur.push(ch);
+    }
+    out.push(cur);
+    out.into_iter().map(|a| a.trim().to_string()).filter(|a| !a.is_empty()).collect()
+}
+
+/// Lists the attachments and keeps the first inline text/plain part (decoded) in `text`.
+fn walk_mime_parts(
+    headers: &[(String, String)],
+    body: &str,
+    depth: usize,
+    out: &mut Vec<EmailAttachment>,
+    text: &mut Option<String>,
+) {
+    let ctype = mail_header(headers, "content-type").unwrap_or("text/plain");
+    let mime = ctype.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+
+    if mime.starts_with("multipart/") {
+        let Some(boundary) = mime_param(ctype, "boundary") else { return; };
+        if depth >= EMAIL_MAX_MIME_DEPTH { return; }
+        let delimiter = format!("--{boundary}");
+        // parts[0] is the preamble; the close delimiter leaves an epilogue starting with "--".
+        for part in body.split(delimiter.as_str()).skip(1) {
+            if part.starts_with("--") { break; }
+            let part = part.strip_prefix('\n').unwrap_or(part);
+            let (head, part_body) = part.split_once("\n\n").unwrap_or((part, ""));
+            walk_mime_parts(&parse_mail_headers(head), part_body, depth + 1, out, text);
+        }
+        return;
+    }
+
+    let disposition = mail_header(headers, "content-disposition").unwrap_or("");
+    let name = mime_param(disposition, "filename").or_else(|| mime_param(ctype, "name"));
+    // A single-part message is the body itself, never an attachment.
+    let is_attachment = depth > 0
+        && (disposition.trim_start().to_ascii_lowercase().starts_with("attachment")
+            || name.is_some()
+            || mime == "message/rfc822");
+    if is_attachment {
+        out.push(EmailAttachment { name: name.unwrap_or_default(), mime });
+    } else if mime == "text/plain" && text.is_none() {
+        *text = Some(decode_mime_text(headers, ctype, body));
+    }
+}
+
+/// Undoes the transfer encoding (base64 / quoted-printable) of a text part.
+fn decode_mime_text(headers: &[(String, String)], ctype: &str, body: &str) -> String {
+    let encoding = mail_header(headers, "content-transfer-encoding").unwrap_or("").trim().to_ascii_lowercase();
+    let bytes = match encoding.as_str() {
+        "base64" => base64::decode(body.split_whitespace().collect::<String>()).unwrap_or_default(),
+        "quoted-printable" => unescape_hex(&body.replace("=\n", ""), b'='),
+        _ => body.as_bytes().to_vec(),
+    };
+    match mime_param(ctype, "charset").unwrap_or_default().to_ascii_lowercase().as_str() {
+        "iso-8859-1" | "latin1" | "windows-1252" => bytes.iter().map(|&b| b as char).collect(),
+        _ => String::from_utf8_lossy(&bytes).to_string(),
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Image embedded keywords (XMP dc:subject/dc:description, IPTC 2:25/2:120)
+// -----------------------------------------------------------------------------
+
+pub(crate) static XMP_PACKET_RE: Lazy<regex::bytes::Regex> =
+    Lazy::new(|| regex::bytes::Regex::new(r"(?s)<x:xmpmeta.*?</x:xmpmeta>").unwrap());
+pub(crate) static XMP_SUBJECT_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<dc:subject>(.*?)</dc:subject>").unwrap());
+static XMP_DESCRIPTION_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<dc:description>(.*?)</dc:description>").unwrap());
+pub(crate) static XMP_LI_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<rdf:li[^>]*>(.*?)</rdf:li>").unwrap());
+
+fn enrich_image_embedded_keywords(path: &str, out: &mut MediaAnalysis) {
+    if let Ok(data) = fs::read(path) {
+        apply_embedded_keywords(&data, out);
+    }
+}
+
+/// Human-assigned keywords are pushed whole; captions go through the usual word split.
+pub(crate) fn apply_embedded_keywords(data: &[u8], out: &mut MediaAnalysis) {
+    let mut keywords = vec![];
+    let mut captions = vec![];
+
+    if let Some(xmp) = XMP_PACKET_RE.find(data).and_then(|m| std::str::from_utf8(m.as_bytes()).ok()) {
+        // Photo Sphere metadata (Google Camera, Insta360, Ricoh Theta, most stitchers).
+        if xmp.contains("GPano:") { out.image.is_360 = true; }
+        for block in XMP_SUBJECT_RE.captures_iter(xmp) {
+            keywords.extend(XMP_LI_RE.captures_iter(&block[1]).map(|li| xml_unescape(&li[1])));
+        }
+        for block in XMP_DESCRIPTION_RE.captures_iter(xmp) {
+            captions.extend(XMP_LI_RE.captures_iter(&block[1]).map(|li| xml_unescape(&li[1])));
+        }
+    }
+    for (dataset, value) in iptc_records(data).unwrap_or_default() {
+        match dataset {
+            25 => keywords.push(value),
+            120 => captions.push(value),
+            _ => {}
+        }
+    }
+
+    for k in keywords {
+        let k = k.trim().to_lowercase();
+        if !k.is_empty() && !out.tagging.raw_keywords.iter().any(|x| x.eq_ignore_ascii_case(&k)) {
+            out.tagging.raw_keywords.push(k);
+        }
+    }
+    for c in captions {
+        maybe_push_kw(&mut out.tagging.raw_keywords, &c);
+    }
+}
+
+/// IIM record-2 datasets from the Photoshop IRB (8BIM resource 0x0404) that JPEGs carry in APP13.
+fn iptc_records(data: &[u8]) -> Option<Vec<(u8, String)>> {
+    let mut i = data.windows(6).position(|w| w == b"8BIM\x04\x04")? + 6;
+    // Pascal-string resource name, padded to an even length.
+    let name_len = *data.get(i)? as usize;
+    i += (name_len + 2) & !1;
+    let size = u32::from_be_bytes(data.get(i..i + 4)?.try_into().ok()?) as usize;
+    i += 4;
+    let block = data.get(i..i.checked_add(size)?)?;
+
+    let mut records = vec![];
+    let mut j = 0;
+    while j + 5 <= block.len() && block[j] == 0x1C {
+        let (record, dataset) = (block[j + 1], block[j + 2]);
+        let len = u16::from_be_bytes([block[j + 3], block[j + 4]]) as usize;
+        let Some(value) = block.get(j + 5..j + 5 + len) else { break };
+        if record == 2 {
+            records.push((dataset, String::from_utf8_lossy(value).into_owned()));
+        }
+        j += 5 + len;
+    }
+    Some(records)
+}
+
+pub(crate) fn xml_unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+// Common desktop and phone screen sizes (landscape; portrait is checked by swapping).
+const SCREEN_RESOLUTIONS: &[(u32, u32)] = &[
+    (1280, 720), (1280, 800), (1366, 768), (1440, 900), (1536, 864), (1600, 900),
+    (1680, 1050), (1920, 1080), (1920, 1200), (2560, 1440), (2560, 1600), (2880, 1800),
+    (3024, 1964), (3456, 2234), (3840, 2160), (5120, 2880),
+    (1334, 750), (1792, 828), (2340, 1080), (2400, 1080), (2436, 1125),
+    (2532, 1170), (2556, 1179), (2208, 1242), (2688, 1242), (2778, 1284), (2796, 1290),
+    (3120, 1440), (3200, 1440),
+];
+// SCREEN_RESOLUTIONS plus TAGGER_SCREEN_RESOLUTIONS (e.g. "2732x2048,1024x600"), either orientation.
+static SCREEN_RESOLUTIONS_ALL: Lazy<Vec<(u32, u32)>> = Lazy::new(|| {
+    let mut all = SCREEN_RESOLUTIONS.to_vec();
+    let Ok(raw) = std::env::var("TAGGER_SCREEN_RESOLUTIONS") else { return all; };
+    for item in raw.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let parsed = item.split_once(['x', 'X'])
+            .and_then(|(w, h)| Some((w.trim().parse::<u32>().ok()?, h.trim().parse::<u32>().ok()?)))
+            .filter(|&(w, h)| w > 0 && h > 0);
+        match parsed {
+            Some(res) => all.push(res),
+            None => log::warn!("[analyse] TAGGER_SCREEN_RESOLUTIONS: ignoring {item:?}, expected WIDTHxHEIGHT"),
+        }
+    }
+    all
+});
+static SCREENSHOT_NAME_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)screenshot|screen[ _-]shot|capture").unwrap());
+
+/// A filename match always counts. Otherwise the image needs an exact screen size, no camera
+/// EXIF and a lossless capture format (PNG/WebP): a 1920x1080 JPEG is more often a video
+/// still or a resized photo.
+fn detect_screenshot(name: &str, out: &mut MediaAnalysis) {
+    let has_camera = out.image.camera_make.is_some() || out.image.camera_model.is_some();
+    let capture_format = matches!(out.meta.mime.as_deref(), Some("image/png" | "image/webp"));
+    let screen_dims = match (out.image.width, out.image.height) {
+        (Some(w), Some(h)) => SCREEN_RESOLUTIONS_ALL.iter().any(|&(sw, sh)| (w, h) == (sw, sh) || (w, h) == (sh, sw)),
+        _ => false,
+    };
+
+    let (is_shot, reason) = if SCREENSHOT_NAME_RE.is_match(name) {
+        (true, "filename looks like a screenshot".to_string())
+    } else if screen_dims && !has_camera && capture_format {
+        let (w, h) = (out.image.width.unwrap_or(0), out.image.height.unwrap_or(0));
+        (true, format!("{w}x{h} matches a screen resolution and has no camera EXIF"))
+    } else if has_camera {
+        (false, "camera EXIF present".to_string())
+    } else if screen_dims {
+        (false, "screen-sized but not PNG/WebP".to_string())
+    } else if out.image.width.is_some() {
+        (false, "dimensions do not match a known screen".to_string())
+    } else {
+        return;
+    };
+
+    out.image.is_screenshot = Some(is_shot);
+    out.image.screenshot_reason = Some(reason);
+    if is_shot && !out.tagging.raw_keywords.iter().any(|k| k == "screenshot") {
+        out.tagging.raw_keywords.push("screenshot".to_string());
+    }
+}
+
+static PANORAMA_NAME_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)(^|[^0-9])360([^0-9]|$)|panorama").unwrap());
+const EQUIRECT_RATIO_TOLERANCE: f64 = 0.02;
+
+fn mentions_360(s: &str) -> bool {
+    let s = s.to_ascii_lowercase();
+    s.contains("360") || s.contains("equirectangular")
+}
+
+/// Equirectangular 360° media. Metadata signals (EXIF Make/Software, XMP GPano, spherical
+/// video side data) are set while parsing; this adds the 2:1 frame and the filename, then
+/// tags whatever was found.
+fn detect_360_media(name: &str, out: &mut MediaAnalysis) {
+    let is_video = out.meta.file_type == MetaFileType::Video;
+    let (w, h) = if is_video { (out.video.width, out.video.height) } else { (out.image.width, out.image.height) };
+    let equirect = match (w, h) {
+        (Some(w), Some(h)) if h > 0 => (w as f64 / h as f64 / 2.0 - 1.0).abs() <= EQUIRECT_RATIO_TOLERANCE,
+        _ => false,
+    };
+    let hit = equirect || PANORAMA_NAME_RE.is_match(name);
+    let is_360 = if is_video { &mut out.video.is_360 } else { &mut out.image.is_360 };
+    *is_360 |= hit;
+    if !*is_360 { return; }
+    for k in ["360-degree", "panorama"] {
+        if !out.tagging.raw_keywords.iter().any(|x| x == k) { out.tagging.raw_keywords.push(k.to_string()); }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Video numeric (ffprobe)
+// -----------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+struct FfStream {
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    avg_frame_rate: Option<String>,
+    pix_fmt: Option<String>,
+    color_space: Option<String>,
+    color_transfer: Option<String>,
+    color_primaries: Option<String>,
+    bit_rate: Option<String>,
+    #[serde(default)] tags: HashMap<String, String>,
+    #[serde(default)] disposition: HashMap<String, i64>,
+    #[serde(default)] side_data_list: Vec<HashMap<String, serde_json::Value>>,
+}
+#[derive(Deserialize)]
+struct FfFormat {
+    duration: Option<String>,
+    bit_rate: Option<String>,
+    #[serde(default)] tags: HashMap<String, String>,
+}
+#[derive(Deserialize)]
+struct FfChapter {
+    start_time: Option<String>,
+    end_time: Option<String>,
+    #[serde(default)] tags: HashMap<String, String>,
+}
+#[derive(Deserialize)]
+struct FfProbe { streams: Option<Vec<FfStream>>, format: Option<FfFormat>, chapters: Option<Vec<FfChapter>> }
+
+static FFPROBE_CORRUPT_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)invalid data found|moov atom not found|truncat|could not find codec parameters|error reading header|ebml header parsing failed").unwrap()
+});
+
+/// Above this frame rate footage is high-speed capture meant for slowed playback.
+const SLOW_MOTION_MIN_FPS: f64 = 60.0;
+
+fn enrich_video_ffprobe(path: &str, out: &mut MediaAnalysis) -> anyhow::Result<()> {
+    let ff = find_tool("ffprobe").ok_or_else(|| anyhow::anyhow!("ffprobe not found (set FFPROBE_PATH or add it to PATH)"))?;
+    let permit = ProcessPermit::acquire();
+    let output = output_with_timeout(
+        Command::new(&ff).args(["-v","error","-print_format","json","-show_format","-show_streams","-show_chapters",path]),
+        "ffprobe",
+    ).map_err(video_tool_error)?;
+    drop(permit); // the decode check below takes its own
+    if !output.status.success() {
+        // ffprobe could open the file but not make sense of it: broken container/stream data.
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if FFPROBE_CORRUPT_RE.is_match(&stderr) {
+            out.meta.is_corrupt = Some(true);
+        }
+        return Err(anyhow::anyhow!("ffprobe failed: {}", stderr.trim()));
+    }
+    out.meta.is_corrupt = Some(false);
+    let parsed: FfProbe = serde_json::from_slice(&output.stdout)?;
+
+    if let Some(fmt) = &parsed.format {
+        if let Some(d) = &fmt.duration { if let Ok(secs) = d.parse::<f64>() { out.video.duration_sec = Some(secs); } }
+    }
+    let video_stream = parsed.streams.as_deref().unwrap_or_default()
+        .iter()
+        .find(|s| s.codec_type.as_deref() == Some("video"));
+    if let Some(vs) = video_stream {
+        out.video.codec  = vs.codec_name.clone();
+        out.video.width  = vs.width;
+        out.video.height = vs.height;
+        if let Some(r) = &vs.avg_frame_rate { if let Some(fps) = parse_rational(r) { out.video.fps = Some(fps); } }
+        out.video.pix_fmt         = vs.pix_fmt.clone();
+        out.video.color_space     = vs.color_space.clone();
+        out.video.color_transfer  = vs.color_transfer.clone();
+        out.video.color_primaries = vs.color_primaries.clone();
+        // PQ (HDR10/Dolby Vision), HLG and DCI-XYZ transfer curves
+        out.video.is_hdr = matches!(
+            vs.color_transfer.as_deref(),
+            Some("smpte2084" | "arib-std-b67" | "smpte428")
+        );
+        // Spherical Video V2 (YouTube/GoPro/Insta360 exports) surfaces as stream side data.
+        out.video.is_360 = vs.side_data_list.iter()
+            .any(|d| d.get("side_data_type").and_then(|t| t.as_str()) == Some("Spherical Mapping"));
+    }
+
+    // Capture time: Apple's local-time tag beats the generic UTC one; streams are the fallback.
+    let format_tags = parsed.format.as_ref().map(|f| &f.tags);
+    out.video.recorded_at = format_tags
+        .into_iter()
+        .chain(video_stream.map(|s| &s.tags))
+        .flat_map(|tags| ["com.apple.quicktime.creationdate", "creation_time"].map(|k| tags.get(k)))
+        .flatten()
+        .find_map(|v| normalize_datetime(v));
+
+    // Slo-Mo exports keep their capture rate (120/240 fps). Android also records the sensor
+    // rate separately, which is the only way to tell a time-lapse: it plays back far faster
+    // than it was shot.
+    let capture_fps = format_tags
+        .into_iter()
+        .chain(video_stream.map(|s| &s.tags))
+        .find_map(|tags| tags.get("com.android.capture.fps"))
+        .and_then(|v| v.parse::<f64>().ok())
+        .filter(|f| *f > 0.0);
+    if let Some(fps) = out.video.fps {
+        out.video.is_slow_motion = fps > SLOW_MOTION_MIN_FPS
+            || capture_fps.is_some_and(|c| c > SLOW_MOTION_MIN_FPS && c > fps * 1.5);
+        out.video.is_timelapse = capture_fps.is_some_and(|c| c < fps / 2.0);
+    }
+
+    // "und" is ffprobe's placeholder for an untagged track, not a language.
+    out.video.subtitle_tracks = parsed.streams.as_deref().unwrap_or_default()
+        .iter()
+        .filter(|s| s.codec_type.as_deref() == Some("subtitle"))
+        .map(|s| SubtitleTrack {
+            language: s.tags.get("language").filter(|l| !l.is_empty() && l.as_str() != "und").cloned(),
+            codec: s.codec_name.clone(),
+            is_forced: s.disposition.get("forced").is_some_and(|&f| f != 0),
+        })
+        .collect();
+
+    // MP4 chapter tracks and MKV chapter atoms alike; untitled chapters are kept so the
+    // segment boundaries still line up.
+    for ch in parsed.chapters.unwrap_or_default() {
+        let secs = |t: &Option<String>| t.as_deref().and_then(|t| t.parse::<f64>().ok());
+        let (Some(start_sec), Some(end_sec)) = (secs(&ch.start_time), secs(&ch.end_time)) else { continue; };
+        let title = ch.tags.get("title").map(|t| t.trim().to_string()).unwrap_or_default();
+        maybe_push_kw(&mut out.tagging.raw_keywords, &title);
+        out.video.chapters.push(VideoChapter { title, start_sec, end_sec });
+    }
+
+    if let (Some(vs), Some(w), Some(h)) = (video_stream, out.video.width, out.video.height) {
+        // Not every container reports a per-stream rate (MKV); the overall rate includes
+        // audio but is close enough.
+        let bit_rate = vs.bit_rate.as_deref()
+            .or(parsed.format.as_ref().and_then(|f| f.bit_rate.as_deref()))
+            .and_then(|b| b.parse::<f64>().ok());
+        out.meta.quality_score = Some(video_quality_score(w, h, out.video.fps, bit_rate));
+    }
+
+    if video_stream.is_some() {
+        check_video_decode(&ff, path, out);
+    }
+    Ok(())
+}
+
+const VIDEO_DECODE_CHECK_SECS: u32 = 5;
+const VIDEO_DECODE_MAX_WARNINGS: usize = 5;
+const VIDEO_DECODE_WARNING_CHARS: usize = 200;
+
+/// Decodes the first seconds of the first video stream: a truncated download usually
+/// probes fine but fails here.
+fn check_video_decode(ff: &Path, path: &str, out: &mut MediaAnalysis) {
+    let _permit = ProcessPermit::acquire();
+    let output = output_with_timeout(
+        Command::new(ff)
+            .args(["-v", "error", "-select_streams", "v:0", "-count_frames", "-show_entries", "stream=nb_read_frames",
+                   "-of", "csv=p=0", "-read_intervals"])
+            .arg(format!("%+{VIDEO_DECODE_CHECK_SECS}"))
+            .arg(path),
+        "ffprobe",
+    );
+    let output = match output {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+            out.warnings.push(format!("video decode check skipped: {e}"));
+            return;
+        }
+        Err(_) => return,
+    };
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let errors: Vec<&str> = stderr.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    if errors.is_empty() { return; }
+    out.video.has_decode_errors = true;
+    for line in errors.iter().take(VIDEO_DECODE_MAX_WARNINGS) {
+        let line: String = line.chars().take(VIDEO_DECODE_WARNING_CHARS).collect();
+        out.warnings.push(format!("video decode error: {line}"));
+    }
+    if errors.len() > VIDEO_DECODE_MAX_WARNINGS {
+        out.warnings.push(format!("video decode error: ... {} more", errors.len() - VIDEO_DECODE_MAX_WARNINGS));
+    }
+}
+
+fn parse_rational(s: &str) -> Option<f64> {
+    let mut it = s.split('/');
+    let a = it.next()?.parse::<f64>().ok()?;
+    let b = it.next()?.parse::<f64>().ok()?;
     if b == 0.0 { None } else { Some(a / b) }
 }
 
-// -----------------------------------------------------------------------------
-// PDF numeric (lopdf)
-// -----------------------------------------------------------------------------
-
-fn enrich_pdf_lopdf(path: &str, out: &mut MediaAnalysis) -> anyhow::Result<()> {
-    let doc = lopdf::Document::load(path)?;
-    let pages = doc.get_pages();
-    out.pdf.page_count = Some(pages.len() as u32);
-    if let Some((_, page_id)) = pages.into_iter().next() {
-        if let Ok(page_dict) = doc.get_dictionary(page_id) {
-            if let Ok(mb_obj) = page_dict.get(b"MediaBox") {
-                if let lopdf::Object::Array(arr) = mb_obj {
-                    if arr.len() == 4 {
-                        let w = num_from_pdf(&arr[2])? - num_from_pdf(&arr[0])?;
-                        let h = num_from_pdf(&arr[3])? - num_from_pdf(&arr[1])?;
-                        out.pdf.page0_width_pt  = Some(w);
-                        out.pdf.page0_height_pt = Some(h);
-                    }
-                }
-            }
+// -----------------------------------------------------------------------------
+// PDF numeric (lopdf)
+// -----------------------------------------------------------------------------
+
+const PDF_MAX_PAGE_DIMS: usize = 100;
+
+fn enrich_pdf_lopdf(path: &str, with_text: bool, out: &mut MediaAnalysis) -> anyhow::Result<()> {
+    let doc = lopdf::Document::load(path)?;
+    let pages = doc.get_pages();
+    out.pdf.page_count = Some(pages.len() as u32);
+    for (i, (_, page_id)) in pages.into_iter().take(PDF_MAX_PAGE_DIMS).enumerate() {
+        let Some((w, h)) = page_dimensions(&doc, page_id) else { continue };
+        if i == 0 {
+            out.pdf.page0_width_pt  = Some(w);
+            out.pdf.page0_height_pt = Some(h);
+        }
+        out.pdf.all_page_dimensions.push((w, h));
+    }
+    if let Some(&(w0, h0)) = out.pdf.all_page_dimensions.first() {
+        out.pdf.has_mixed_page_sizes = out.pdf.all_page_dimensions
+            .iter()
+            .any(|&(w, h)| (w - w0).abs() > 1.0 || (h - h0).abs() > 1.0);
+    }
+
+    out.pdf.fonts = pdf_fonts(&doc);
+    out.pdf.outline = pdf_outline(&doc);
+    push_outline_keywords(&out.pdf.outline, PDF_OUTLINE_KEYWORD_DEPTH, &mut out.tagging.raw_keywords);
+
+    if let (Some(&page0), Some(width_pt)) = (doc.get_pages().values().next(), out.pdf.page0_width_pt) {
+        out.meta.quality_score = Some(pdf_quality_score(pdf_page_dpi(&doc, page0, width_pt)));
+    }
+
+    // Text layer, page by page; scanned PDFs have none and rely on OCR instead. With text
+    // extraction (Thorough) counts cover every page; otherwise only the language sample is read.
+    let page_count = out.pdf.page_count.unwrap_or(0);
+    let head_pages = if with_text { PDF_TEXT_PAGES } else { PDF_LANGUAGE_PAGES };
+    let read_pages = if with_text { page_count } else { page_count.min(PDF_LANGUAGE_PAGES) };
+    let (mut words, mut chars) = (0u32, 0u32);
+    let mut head = String::new();
+    for page in 1..=read_pages {
+        let Ok(text) = doc.extract_text(&[page]) else { continue; };
+        if with_text {
+            let (w, c) = text_counts(&text);
+            words += w;
+            chars += c;
+        }
+        if page <= head_pages {
+            head.push_str(&text);
+            head.push('\n');
+        }
+    }
+    if words > 0 {
+        out.pdf.word_count = Some(words);
+        out.pdf.char_count = Some(chars);
+    }
+    detect_language(&head, out);
+    if with_text {
+        let text = head.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !text.is_empty() {
+            out.pdf.text_excerpt = Some(text.chars().take(PDF_TEXT_MAX_CHARS).collect());
+        }
+    }
+    Ok(())
+}
+
+/// Words by Unicode word boundaries (UAX #29: "don't" is one word, punctuation is none, CJK
+/// ideographs count individually), and non-whitespace characters.
+fn text_counts(text: &str) -> (u32, u32) {
+    use unicode_segmentation::UnicodeSegmentation;
+    let words = text.unicode_words().count() as u32;
+    let chars = text.chars().filter(|c| !c.is_whitespace()).count() as u32;
+    (words, chars)
+}
+
+const PDF_LANGUAGE_PAGES: u32 = 3;
+const PDF_TEXT_PAGES: u32 = 20;
+const PDF_TEXT_MAX_CHARS: usize = 4000;
+
+const PDF_OUTLINE_MAX_DEPTH: usize = 3;
+const PDF_OUTLINE_MAX_ENTRIES: usize = 50;
+/// Chapters and sections name topics; deeper bookmarks are mostly "2.3.1 Example" noise.
+const PDF_OUTLINE_KEYWORD_DEPTH: usize = 2;
+
+/// Bookmarks from /Root -> /Outlines, at most 3 levels deep and 50 entries in total.
+fn pdf_outline(doc: &lopdf::Document) -> Vec<PdfOutlineEntry> {
+    let first = doc.catalog().ok()
+        .and_then(|cat| cat.get(b"Outlines").ok())
+        .and_then(|o| doc.dereference(o).ok())
+        .and_then(|(_, o)| o.as_dict().ok())
+        .and_then(|d| d.get(b"First").and_then(|f| f.as_reference()).ok());
+    let mut budget = PDF_OUTLINE_MAX_ENTRIES;
+    first.map(|id| walk_outline(doc, id, 1, &mut budget)).unwrap_or_default()
+}
+fn walk_outline(doc: &lopdf::Document, first: lopdf::ObjectId, depth: usize, budget: &mut usize) -> Vec<PdfOutlineEntry> {
+    let mut entries = vec![];
+    let mut next = Some(first);
+    // The shared budget also protects against /Next cycles in malformed files.
+    while let Some(id) = next {
+        if *budget == 0 { break; }
+        let Ok(item) = doc.get_dictionary(id) else { break };
+        *budget -= 1;
+        let title = item.get(b"Title").ok()
+            .and_then(|t| doc.dereference(t).ok())
+            .and_then(|(_, t)| lopdf::decode_text_string(t).ok())
+            .map(|t| t.trim().to_string())
+            .unwrap_or_default();
+        let children = match item.get(b"First").and_then(|f| f.as_reference()) {
+            Ok(child) if depth < PDF_OUTLINE_MAX_DEPTH => walk_outline(doc, child, depth + 1, budget),
+            _ => vec![],
+        };
+        entries.push(PdfOutlineEntry { title, children });
+        next = item.get(b"Next").and_then(|n| n.as_reference()).ok();
+    }
+    entries
+}
+fn push_outline_keywords(entries: &[PdfOutlineEntry], depth: usize, keywords: &mut Vec<String>) {
+    if depth == 0 { return; }
+    for e in entries {
+        maybe_push_kw(keywords, &e.title);
+        push_outline_keywords(&e.children, depth - 1, keywords);
+    }
+}
+/// Fonts from every page's /Resources -> /Font (inherited resources included), first
+/// use first. A font counts as embedded when its descriptor (for Type0, the descendant
+/// font's) has a font program: /FontFile (Type 1), /FontFile2 (TrueType) or /FontFile3
+/// (CFF/OpenType). Type3 glyphs are content streams inside the PDF, so always embedded.
+fn pdf_fonts(doc: &lopdf::Document) -> Vec<PdfFont> {
+    let mut fonts: Vec<PdfFont> = vec![];
+    for page_id in doc.get_pages().into_values() {
+        let Ok(page_fonts) = doc.get_page_fonts(page_id) else { continue };
+        for font in page_fonts.values() {
+            let font = pdf_font(doc, font);
+            if !fonts.contains(&font) { fonts.push(font); }
+        }
+    }
+    fonts
+}
+fn pdf_font(doc: &lopdf::Document, font: &lopdf::Dictionary) -> PdfFont {
+    let name_of = |key: &[u8]| font.get(key).and_then(|n| n.as_name()).map(|n| String::from_utf8_lossy(n).into_owned()).ok();
+    let subtype = name_of(b"Subtype").unwrap_or_default();
+    let has_program = |font: &lopdf::Dictionary| {
+        font.get(b"FontDescriptor").ok()
+            .and_then(|d| doc.dereference(d).ok())
+            .and_then(|(_, d)| d.as_dict().ok())
+            .is_some_and(|d| [&b"FontFile"[..], b"FontFile2", b"FontFile3"].iter().any(|k| d.has(k)))
+    };
+    let descendant = font.get(b"DescendantFonts").ok()
+        .and_then(|d| doc.dereference(d).ok())
+        .and_then(|(_, d)| d.as_array().ok())
+        .and_then(|a| a.first())
+        .and_then(|d| doc.dereference(d).ok())
+        .and_then(|(_, d)| d.as_dict().ok());
+    let is_embedded = subtype == "Type3" || has_program(font) || descendant.is_some_and(has_program);
+    PdfFont { name: name_of(b"BaseFont").unwrap_or_default(), subtype, is_embedded }
+}
+fn page_dimensions(doc: &lopdf::Document, page_id: lopdf::ObjectId) -> Option<(f64, f64)> {
+    // MediaBox is inheritable: walk up /Parent until a page-tree node defines it.
+    let mut dict = doc.get_dictionary(page_id).ok()?;
+    for _ in 0..32 {
+        if let Ok(mb_obj) = dict.get(b"MediaBox") {
+            let (_, mb_obj) = doc.dereference(mb_obj).ok()?;
+            let arr = mb_obj.as_array().ok()?;
+            if arr.len() != 4 { return None; }
+            let w = num_from_pdf(&arr[2]).ok()? - num_from_pdf(&arr[0]).ok()?;
+            let h = num_from_pdf(&arr[3]).ok()? - num_from_pdf(&arr[1]).ok()?;
+            return Some((w, h));
+        }
+        let parent = dict.get(b"Parent").and_then(|p| p.as_reference()).ok()?;
+        dict = doc.get_dictionary(parent).ok()?;
+    }
+    None
+}
+fn num_from_pdf(obj: &lopdf::Object) -> anyhow::Result<f64> {
+    match obj {
+        lopdf::Object::Integer(i) => Ok(*i as f64),
+        lopdf::Object::Real(f) => Ok(*f as f64),
+        _ => Err(anyhow::anyhow!("not a number")),
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Quality score (0-100, for culling; comparable within a media type, not across)
+// -----------------------------------------------------------------------------
+
+const SHARPNESS_SAMPLE_SIDE: u32 = 1024;
+/// Noise is measured on a native-resolution centre crop: downscaling would average it away.
+const NOISE_SAMPLE_SIDE: u32 = 512;
+const NOISE_BLOCK: u32 = 8;
+/// Share of blocks, smoothest first, the noise estimate is taken from.
+const NOISE_SMOOTH_FRACTION: f64 = 0.25;
+/// Noise sigma (8-bit gray levels) that maps to noise_level 1.0, about ISO 6400 on a phone.
+const NOISE_SIGMA_FULL: f64 = 12.0;
+
+// TAGGER_SKIP_QUALITY=1 skips sharpness, noise and the image quality score.
+static SKIP_QUALITY: Lazy<bool> = Lazy::new(|| {
+    std::env::var("TAGGER_SKIP_QUALITY").is_ok_and(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true"))
+});
+
+/// `x` on a log scale between `lo` (0) and `hi` (1), clamped.
+fn log_scale(x: f64, lo: f64, hi: f64) -> f64 {
+    if x <= lo { return 0.0; }
+    ((x / lo).ln() / (hi / lo).ln()).min(1.0)
+}
+
+fn round_score(unit: f64) -> f32 {
+    ((unit * 1000.0).round() / 10.0) as f32
+}
+
+/// Variance of the 4-neighbour Laplacian over a grayscale sample: low for blurred or
+/// out-of-focus shots, high for crisp edges. Sampled at a fixed size so files compare.
+fn laplacian_variance(img: &image::DynamicImage) -> f64 {
+    let gray = img.thumbnail(SHARPNESS_SAMPLE_SIDE, SHARPNESS_SAMPLE_SIDE).to_luma8();
+    let (w, h) = gray.dimensions();
+    if w < 3 || h < 3 { return 0.0; }
+    let px = |x: u32, y: u32| gray.get_pixel(x, y).0[0] as f64;
+    let (mut sum, mut sum_sq, mut n) = (0.0, 0.0, 0.0);
+    for y in 1..h - 1 {
+        for x in 1..w - 1 {
+            let lap = px(x - 1, y) + px(x + 1, y) + px(x, y - 1) + px(x, y + 1) - 4.0 * px(x, y);
+            sum += lap;
+            sum_sq += lap * lap;
+            n += 1.0;
+        }
+    }
+    let mean = sum / n;
+    sum_sq / n - mean * mean
+}
+
+/// Gaussian noise in [0, 1] (0 = clean), after Liu et al.: estimate from weak-texture
+/// patches only, so edges and detail don't pass for noise. Each 8x8 block's sigma comes from
+/// Immerkær's residual mask (which cancels smooth gradients); the smoothest quarter of the
+/// blocks is averaged.
+fn noise_level(img: &image::DynamicImage) -> f64 {
+    let (w, h) = (img.width(), img.height());
+    let (cw, ch) = (w.min(NOISE_SAMPLE_SIDE), h.min(NOISE_SAMPLE_SIDE));
+    if cw < 3 || ch < 3 { return 0.0; }
+    let gray = img.crop_imm((w - cw) / 2, (h - ch) / 2, cw, ch).to_luma8();
+    let px = |x: u32, y: u32| gray.get_pixel(x, y).0[0] as f64;
+    let mut sigmas = vec![];
+    for by in (1..ch - 1).step_by(NOISE_BLOCK as usize) {
+        for bx in (1..cw - 1).step_by(NOISE_BLOCK as usize) {
+            let (mut sum, mut n) = (0.0, 0.0);
+            for y in by..(by + NOISE_BLOCK).min(ch - 1) {
+                for x in bx..(bx + NOISE_BLOCK).min(cw - 1) {
+                    let r = px(x - 1, y - 1) + px(x + 1, y - 1) + px(x - 1, y + 1) + px(x + 1, y + 1)
+                        - 2.0 * (px(x, y - 1) + px(x - 1, y) + px(x + 1, y) + px(x, y + 1))
+                        + 4.0 * px(x, y);
+                    sum += r.abs();
+                    n += 1.0;
+                }
+            }
+            sigmas.push((std::f64::consts::PI / 2.0).sqrt() * sum / (6.0 * n));
+        }
+    }
+    sigmas.sort_by(f64::total_cmp);
+    let k = ((sigmas.len() as f64 * NOISE_SMOOTH_FRACTION).ceil() as usize).clamp(1, sigmas.len());
+    let sigma = sigmas[..k].iter().sum::<f64>() / k as f64;
+    (sigma / NOISE_SIGMA_FULL).min(1.0)
+}
+
+/// Sharpness, noise and the quality score, on the preview decode. Off with TAGGER_SKIP_QUALITY.
+fn enrich_image_quality(img: &image::DynamicImage, out: &mut MediaAnalysis) {
+    if *SKIP_QUALITY { return; }
+    // RAW previews are smaller than the sensor; score the real resolution when known.
+    let (w, h) = (out.image.width.unwrap_or(img.width()), out.image.height.unwrap_or(img.height()));
+    let sharpness = laplacian_variance(img);
+    out.image.sharpness = Some(sharpness as f32);
+    out.image.noise_level = Some(noise_level(img));
+    out.meta.quality_score = Some(image_quality_score(w, h, sharpness));
+}
+
+/// 40% resolution (0.3 MP to 24 MP), 60% sharpness: a blurred 40 MP shot is still a reject.
+fn image_quality_score(width: u32, height: u32, sharpness: f64) -> f32 {
+    let megapixels = width as f64 * height as f64 / 1e6;
+    round_score(0.4 * log_scale(megapixels, 0.3, 24.0) + 0.6 * log_scale(sharpness, 20.0, 2000.0))
+}
+
+/// Half resolution (360p to 2160p), half bits per pixel per frame (0.01 starved to 0.2
+/// generous); resolution alone when the bitrate is unknown.
+fn video_quality_score(width: u32, height: u32, fps: Option<f64>, bit_rate: Option<f64>) -> f32 {
+    let pixels = width as f64 * height as f64;
+    let resolution = log_scale(pixels, 640.0 * 360.0, 3840.0 * 2160.0);
+    let fps = fps.filter(|f| *f > 0.0).unwrap_or(30.0);
+    match bit_rate.filter(|_| pixels > 0.0) {
+        Some(bps) => round_score(0.5 * resolution + 0.5 * log_scale(bps / (pixels * fps), 0.01, 0.2)),
+        None => round_score(resolution),
+    }
+}
+
+/// Scan resolution from 72 (0) to 300 DPI (100). Pages without raster images are born
+/// digital and score 100.
+fn pdf_quality_score(dpi: Option<f64>) -> f32 {
+    match dpi {
+        Some(dpi) => round_score(((dpi - 72.0) / (300.0 - 72.0)).clamp(0.0, 1.0)),
+        None => 100.0,
+    }
+}
+
+/// Effective DPI of the widest image on the page, taking it as spanning the page width
+/// (true for scans, generous for photos placed in a layout).
+fn pdf_page_dpi(doc: &lopdf::Document, page_id: lopdf::ObjectId, width_pt: f64) -> Option<f64> {
+    let widest = doc.get_page_images(page_id).ok()?.iter().map(|i| i.width).max()?;
+    (width_pt > 0.0 && widest > 0).then(|| widest as f64 / (width_pt / 72.0))
+}
+
+// -----------------------------------------------------------------------------
+// Real-media previews for AI (actual pixels/frames/pages)
+// -----------------------------------------------------------------------------
+
+/// Pixel budgets for the AI previews; Thorough trades payload size for detail.
+struct PreviewSizes { image_max_side: u32, video_frame_width: u32, pdf_max_width: u32 }
+
+fn preview_sizes(profile: AnalysisProfile) -> PreviewSizes {
+    match profile {
+        AnalysisProfile::Thorough => PreviewSizes { image_max_side: 3072, video_frame_width: 1024, pdf_max_width: 2200 },
+        _ => PreviewSizes { image_max_side: 2048, video_frame_width: 512, pdf_max_width: 1400 },
+    }
+}
+
+fn prepare_media_previews(
+    file: &LoadedFile,
+    mime: Option<&str>,
+    opts: &AnalyseOptions,
+    analysis: &mut MediaAnalysis,
+) -> Result<MediaPreviews, tauri::Error> {
+    let lower = mime.unwrap_or("").to_lowercase();
+    let ext = Path::new(&file.name).extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    let is_image = lower.starts_with("image/") || matches!(ext.as_str(), "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp" | "tif" | "tiff" | "avif");
+    let is_video = lower.starts_with("video/") || matches!(ext.as_str(), "mp4" | "mov" | "avi" | "mkv" | "webm");
+    let is_pdf   = lower == "application/pdf" || ext == "pdf";
+
+    let is_raw = matches!(get_type(&file.name), FileType::RawImage);
+    let is_svg = matches!(get_type(&file.name), FileType::Svg);
+
+    let sizes = preview_sizes(opts.profile);
+    let mut out = MediaPreviews::default();
+
+    // These previews are written to disk by an external tool first.
+    if is_svg || is_video || is_pdf {
+        if let Some(msg) = temp_space_shortfall() {
+            log::warn!("[analyse {}] skipping preview for {}: {msg}", job::current(), file.name);
+            analysis.warnings.push(format!("preview skipped: {msg}"));
+            return Ok(out);
+        }
+    }
+
+    if is_svg {
+        // No rasterizer installed: the AI works from the title/description alone.
+        if let Some(img) = rasterize_svg(&file.path, sizes.image_max_side)? {
+            let (b64, mime) = downscale_image_b64(img, sizes.image_max_side, PreviewEncoding::from(opts))?;
+            out.image_b64 = Some(b64);
+            out.image_mime = Some(mime.to_string());
+        }
+    } else if is_image || is_raw {
+        let img = if is_raw {
+            raw_embedded_preview(&file.path)
+        } else if ext == "avif" && !cfg!(feature = "avif") {
+            // ImageFormat::Avif.reading_enabled() is true whenever the encoder is built in.
+            log::warn!("[analyse {}] {}: built without AVIF decoding; metadata only", job::current(), file.name);
+            analysis.warnings.push("AVIF preview unavailable: built without the `avif` feature".to_string());
+            None
+        } else {
+            decode_checked(image::open(&file.path), analysis)
+        };
+        if let Some(img) = img {
+            enrich_image_pixels(&img, opts, analysis);
+            let (b64, mime) = downscale_image_b64(img, sizes.image_max_side, PreviewEncoding::from(opts))?; // real pixels; capped for bandwidth
+            out.image_b64 = Some(b64);
+            out.image_mime = Some(mime.to_string());
+        }
+    } else if is_video {
+        let (frames, mime) = extract_video_keyframes_b64(&file.path, 6, sizes.video_frame_width, PreviewEncoding::from(opts))?; // real frames
+        if opts.profile == AnalysisProfile::Thorough {
+            analysis.video.phash = video_phash_b64(&frames);
+        }
+        out.video_frames_b64 = Some(frames);
+        out.video_frames_mime = Some(mime.to_string());
+    } else if is_pdf {
+        // real page pixels
+        if let Some((b64, mime, px)) = rasterize_pdf_page0_b64(&file.path, sizes.pdf_max_width, PreviewEncoding::from(opts))? {
+            analysis.pdf.preview_dpi = pdf_preview_dpi(px, analysis);
+            out.pdf_page0_b64 = Some(b64);
+            out.pdf_page0_mime = Some(mime.to_string());
+        }
+    }
+
+    Ok(out)
+}
+
+const GRAY_SAMPLE_SIDE: u32 = 64;
+/// Downscaling averages alpha, so a transparent region still shows up below 255.
+const TRANSPARENCY_SAMPLE_SIDE: u32 = 256;
+const GRAY_CHANNEL_TOLERANCE: u8 = 16;
+
+/// Facts that need decoded pixels; runs on the decode already done for the preview.
+fn enrich_image_pixels(img: &image::DynamicImage, opts: &AnalyseOptions, out: &mut MediaAnalysis) {
+    use image::ColorType;
+    if opts.profile == AnalysisProfile::Thorough {
+        out.image.phash = Some(phash_b64(img));
+    }
+    out.image.histogram = Some(rgb_histogram(img));
+    out.image.dominant_colors = dominant_colors(img, opts.color_count, opts.color_format);
+    enrich_image_quality(img, out);
+    out.image.has_transparency = Some(img.color().has_alpha() && {
+        let fits = img.width() <= TRANSPARENCY_SAMPLE_SIDE && img.height() <= TRANSPARENCY_SAMPLE_SIDE;
+        if fits { has_transparency(img) } else { has_transparency(&img.thumbnail(TRANSPARENCY_SAMPLE_SIDE, TRANSPARENCY_SAMPLE_SIDE)) }
+    });
+    out.image.is_grayscale = Some(match img.color() {
+        ColorType::L8 | ColorType::La8 | ColorType::L16 | ColorType::La16 => true,
+        // RGB containers can still be visually monochrome (scans, B&W exports). Allow a
+        // small share of off pixels for JPEG chroma noise.
+        _ => {
+            let sample = img.thumbnail(GRAY_SAMPLE_SIDE, GRAY_SAMPLE_SIDE).to_rgb8();
+            let total = sample.pixels().len().max(1);
+            let colored = sample.pixels().filter(|p| {
+                let [r, g, b] = p.0;
+                r.max(g).max(b) - r.min(g).min(b) > GRAY_CHANNEL_TOLERANCE
+            }).count();
+            colored * 100 <= total
+        }
+    });
+}
+
+const DOMINANT_COLOR_SAMPLE_SIDE: u32 = 256;
+
+/// Up to `count` palette colours (color-thief median cut), most common first. Shares come
+/// from assigning every sampled pixel to its nearest palette colour, so they add up to
+/// about 100 but are approximate; color-thief itself skips near-white pixels.
+fn dominant_colors(img: &image::DynamicImage, count: usize, format: ColorFormat) -> Vec<DominantColor> {
+    if count == 0 { return vec![]; }
+    let sample = img.thumbnail(DOMINANT_COLOR_SAMPLE_SIDE, DOMINANT_COLOR_SAMPLE_SIDE).to_rgb8();
+    // color-thief wants 2..=255 colours and may return fewer.
+    let max_colors = count.clamp(2, 255) as u8;
+    let Ok(palette) = color_thief::get_palette(sample.as_raw(), color_thief::ColorFormat::Rgb, 1, max_colors) else {
+        return vec![];
+    };
+    if palette.is_empty() { return vec![]; }
+
+    let mut counts = vec![0usize; palette.len()];
+    for p in sample.pixels() {
+        let [r, g, b] = p.0.map(i32::from);
+        let nearest = (0..palette.len()).min_by_key(|&i| {
+            let c = &palette[i];
+            (r - c.r as i32).pow(2) + (g - c.g as i32).pow(2) + (b - c.b as i32).pow(2)
+        });
+        if let Some(i) = nearest { counts[i] += 1; }
+    }
+    let total = sample.pixels().len().max(1) as f32;
+    let mut colors: Vec<(usize, DominantColor)> = palette.iter().zip(&counts)
+        .filter(|(_, &n)| n > 0)
+        .map(|(c, &n)| (n, DominantColor { value: format_color([c.r, c.g, c.b], format), percent: n as f32 * 100.0 / total }))
+        .collect();
+    colors.sort_by(|a, b| b.0.cmp(&a.0));
+    colors.into_iter().take(count).map(|(_, c)| c).collect()
+}
+
+/// `#aabbcc`, `rgb(170, 187, 204)` or `hsl(210, 25%, 73%)` (whole degrees and percents).
+fn format_color([r, g, b]: [u8; 3], format: ColorFormat) -> String {
+    match format {
+        ColorFormat::Hex => format!("#{r:02x}{g:02x}{b:02x}"),
+        ColorFormat::Rgb => format!("rgb({r}, {g}, {b})"),
+        ColorFormat::Hsl => {
+            let (r, g, b) = (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+            let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+            let l = (max + min) / 2.0;
+            let d = max - min;
+            let (h, s) = if d == 0.0 {
+                (0.0, 0.0)
+            } else {
+                let h = if max == r { ((g - b) / d).rem_euclid(6.0) } else if max == g { (b - r) / d + 2.0 } else { (r - g) / d + 4.0 };
+                (h * 60.0, d / (1.0 - (2.0 * l - 1.0).abs()))
+            };
+            format!("hsl({:.0}, {:.0}%, {:.0}%)", h, s * 100.0, l * 100.0)
+        }
+    }
+}
+
+/// 64-bit DCT perceptual hash, base64 of the 8 big-endian bytes. Bit i is set when the
+/// i-th low-frequency coefficient (8x8 block of a 32x32 grayscale DCT, DC excluded from
+/// the median) is above the median. Similar images differ in few bits.
+pub(crate) fn phash_b64(img: &image::DynamicImage) -> String {
+    const N: usize = 32;
+    const K: usize = 8;
+    let gray = img.resize_exact(N as u32, N as u32, image::imageops::FilterType::Triangle).to_luma8();
+    let px: Vec<f64> = gray.pixels().map(|p| p.0[0] as f64).collect();
+
+    // Separable DCT-II, low frequencies only.
+    let cos: Vec<f64> = (0..K * N).map(|i| {
+        let (u, x) = (i / N, i % N);
+        (std::f64::consts::PI * (2 * x + 1) as f64 * u as f64 / (2 * N) as f64).cos()
+    }).collect();
+    let mut rows = vec![0.0f64; N * K]; // rows[y * K + u]
+    for y in 0..N {
+        for u in 0..K {
+            rows[y * K + u] = (0..N).map(|x| px[y * N + x] * cos[u * N + x]).sum();
+        }
+    }
+    let mut coeffs = [0.0f64; K * K]; // coeffs[v * K + u]
+    for v in 0..K {
+        for u in 0..K {
+            coeffs[v * K + u] = (0..N).map(|y| rows[y * K + u] * cos[v * N + y]).sum();
+        }
+    }
+
+    let mut ac: Vec<f64> = coeffs[1..].to_vec();
+    ac.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let median = ac[ac.len() / 2];
+    let bits = coeffs.iter().fold(0u64, |acc, &c| (acc << 1) | (c > median) as u64);
+    base64::encode(bits.to_be_bytes())
+}
+
+const HISTOGRAM_SAMPLE_SIDE: u32 = 512;
+const HISTOGRAM_BINS: usize = 64;
+
+/// Counts over a downscaled copy of the preview decode, so it costs no extra I/O and stays
+/// cheap enough for every profile that decodes pixels. Compare shapes, not absolute counts.
+fn rgb_histogram(img: &image::DynamicImage) -> Histogram {
+    let sample = img.thumbnail(HISTOGRAM_SAMPLE_SIDE, HISTOGRAM_SAMPLE_SIDE).to_rgb8();
+    let mut h = Histogram { r: vec![0; HISTOGRAM_BINS], g: vec![0; HISTOGRAM_BINS], b: vec![0; HISTOGRAM_BINS] };
+    let bin_width = 256 / HISTOGRAM_BINS;
+    for p in sample.pixels() {
+        let [r, g, b] = p.0;
+        h.r[r as usize / bin_width] += 1;
+        h.g[g as usize / bin_width] += 1;
+        h.b[b as usize / bin_width] += 1;
+    }
+    h
+}
+
+/// QR codes and barcodes via `zbarimg`, as `"<symbology>:<data>"` (e.g. `QR-Code:https://…`).
+/// Best effort: no zbarimg, or nothing found, leaves the list empty.
+fn scan_barcodes(path: &str, out: &mut MediaAnalysis) {
+    let Some(zbarimg) = find_tool("zbarimg") else { return; };
+    let _permit = ProcessPermit::acquire();
+    let output = match output_with_timeout(Command::new(zbarimg).args(["-q", path]), "zbarimg") {
+        Ok(o) => o,
+        Err(e) => {
+            out.warnings.push(format!("barcode scan failed: {e}"));
+            return;
+        }
+    };
+    // Exit status 4 means "no symbols found"; stdout is simply empty then.
+    out.image.barcodes = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect();
+}
+
+/// Full decode that records the outcome on `meta.is_corrupt`: garbled or truncated data
+/// marks the file corrupt, while an unsupported format only leaves a warning.
+fn decode_checked(res: image::ImageResult<image::DynamicImage>, out: &mut MediaAnalysis) -> Option<image::DynamicImage> {
+    use image::ImageError;
+    use std::io::ErrorKind;
+    let e = match res {
+        Ok(img) => {
+            out.meta.is_corrupt = Some(false);
+            return Some(img);
+        }
+        Err(e) => e,
+    };
+    match &e {
+        ImageError::Decoding(_) | ImageError::Parameter(_) => out.meta.is_corrupt = Some(true),
+        ImageError::IoError(io) if matches!(io.kind(), ErrorKind::UnexpectedEof | ErrorKind::InvalidData) => {
+            out.meta.is_corrupt = Some(true)
         }
+        _ => {} // unsupported format, limits, unreadable file: says nothing about the bytes
     }
-    Ok(())
+    out.warnings.push(format!("image decode failed: {e}"));
+    None
 }
-fn num_from_pdf(obj: &lopdf::Object) -> anyhow::Result<f64> {
-    match obj {
-        lopdf::Object::Integer(i) => Ok(*i as f64),
-        lopdf::Object::Real(f) => Ok(*f as f64),
-        _ => Err(anyhow::anyhow!("not a number")),
+
+/// How image previews are encoded; from `AnalyseOptions.preview_format` / `preview_quality`.
+#[derive(Clone, Copy)]
+struct PreviewEncoding { format: PreviewFormat, quality: u8 }
+
+impl From<&AnalyseOptions> for PreviewEncoding {
+    fn from(opts: &AnalyseOptions) -> Self {
+        PreviewEncoding { format: opts.preview_format, quality: opts.preview_quality.clamp(1, 100) }
     }
 }
 
-// -----------------------------------------------------------------------------
-// Real-media previews for AI (actual pixels/frames/pages)
-// -----------------------------------------------------------------------------
+// TAGGER_SMART_CROP=1 crops image previews to their busiest 1:1 or 16:9 window before
+// downscaling, so the subject of a tall or very wide shot isn't shrunk along with the rest.
+static SMART_CROP: Lazy<bool> = Lazy::new(|| {
+    std::env::var("TAGGER_SMART_CROP").is_ok_and(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true"))
+});
 
-#[derive(Default, Serialize)]
-struct MediaPreviews {
-    image_b64: Option<String>,
-    video_frames_b64: Option<Vec<String>>,
-    pdf_page0_b64: Option<String>,
-}
+const SMART_CROP_ASPECTS: [f64; 2] = [1.0, 16.0 / 9.0];
+/// Images already within 5% of the chosen aspect are left alone (a cropped preview that
+/// is re-encoded smaller stays as it is).
+const SMART_CROP_TOLERANCE: f64 = 1.05;
+/// Saliency is measured on a copy this size; the window is scaled back up.
+const SALIENCY_SIDE: u32 = 256;
 
-fn prepare_media_previews(file: &LoadedFile, mime: Option<&str>) -> Result<MediaPreviews, tauri::Error> {
-    let lower = mime.unwrap_or("").to_lowercase();
-    let ext = Path::new(&file.name).extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
-    let is_image = lower.starts_with("image/") || matches!(ext.as_str(), "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp");
-    let is_video = lower.starts_with("video/") || matches!(ext.as_str(), "mp4" | "mov" | "avi" | "mkv" | "webm");
-    let is_pdf   = lower == "application/pdf" || ext == "pdf";
+/// Crops to the 1:1 or 16:9 window (whichever is closer to the image's shape) with the
+/// most edge energy. Sobel gradient magnitude stands in for a saliency map: faces, text
+/// and logos are detailed, sky and walls aren't. Ties go to the window nearest the centre.
+fn smart_crop(img: image::DynamicImage) -> image::DynamicImage {
+    let (w, h) = img.dimensions();
+    if w < 2 || h < 2 { return img; }
+    let aspect = w as f64 / h as f64;
+    let off_by = |target: f64| (aspect / target).ln().abs();
+    let target = SMART_CROP_ASPECTS.into_iter().min_by(|a, b| off_by(*a).total_cmp(&off_by(*b))).unwrap_or(1.0);
+    if off_by(target) <= SMART_CROP_TOLERANCE.ln() { return img; }
 
-    let mut out = MediaPreviews::default();
+    // Wider than the target: the window spans the full height and slides along x.
+    let horizontal = aspect > target;
+    let (len, crop_len) = if horizontal {
+        (w, ((h as f64 * target).round() as u32).clamp(1, w))
+    } else {
+        (h, ((w as f64 / target).round() as u32).clamp(1, h))
+    };
 
-    if is_image {
-        out.image_b64 = Some(read_and_downscale_image_b64(&file.path, 2048)?); // real pixels; capped for bandwidth
-    } else if is_video {
-        out.video_frames_b64 = Some(extract_video_keyframes_b64(&file.path, 6)?); // real frames
-    } else if is_pdf {
-        out.pdf_page0_b64 = rasterize_pdf_page0_b64(&file.path)?; // real page pixels
+    let gray = img.thumbnail(SALIENCY_SIDE, SALIENCY_SIDE).to_luma8();
+    let grad = imageproc::gradients::sobel_gradients(&gray);
+    let (gw, gh) = grad.dimensions();
+    let mut profile = vec![0u64; if horizontal { gw } else { gh } as usize];
+    for (x, y, p) in grad.enumerate_pixels() {
+        profile[if horizontal { x } else { y } as usize] += u64::from(p[0]);
     }
+    let scale = profile.len() as f64 / len as f64;
+    let win = ((crop_len as f64 * scale).round() as usize).clamp(1, profile.len());
 
-    Ok(out)
+    let centre = (profile.len() - win) as f64 / 2.0;
+    let mut sum: u64 = profile[..win].iter().sum();
+    let mut best = (sum, 0usize);
+    for start in 1..=profile.len() - win {
+        sum = sum + profile[start + win - 1] - profile[start - 1];
+        let closer = (start as f64 - centre).abs() < (best.1 as f64 - centre).abs();
+        if sum > best.0 || (sum == best.0 && closer) { best = (sum, start); }
+    }
+    let offset = ((best.1 as f64 / scale).round() as u32).min(len - crop_len);
+    if horizontal { img.crop_imm(offset, 0, crop_len, h) } else { img.crop_imm(0, offset, w, crop_len) }
 }
 
-fn read_and_downscale_image_b64(path: &str, max_side: u32) -> Result<String, tauri::Error> {
-    let img = image::open(path).map_err(|e| ioerr(format!("image open: {e}")))?;
+/// Downscales to `max_side` and encodes; returns the base64 and its MIME type. PNG of a
+/// photo is several times the size of a decent JPEG, so Auto only keeps PNG for images
+/// that actually use transparency.
+fn downscale_image_b64(img: image::DynamicImage, max_side: u32, enc: PreviewEncoding) -> Result<(String, &'static str), tauri::Error> {
+    let img = if *SMART_CROP { smart_crop(img) } else { img };
     let (w, h) = img.dimensions();
     let (nw, nh) = if w.max(h) > max_side {
         if w >= h { (max_side, ((h as f32 * max_side as f32 / w as f32).round() as u32).max(1)) }
@@ -289,68 +2620,403 @@ fn read_and_downscale_image_b64(path: &str, max_side: u32) -> Result<String, tau
     } else { (w, h) };
     let small = img.resize_exact(nw, nh, image::imageops::FilterType::CatmullRom);
 
+    let format = match enc.format {
+        PreviewFormat::Auto if has_transparency(&small) => PreviewFormat::Png,
+        PreviewFormat::Auto => PreviewFormat::Jpeg,
+        f => f,
+    };
+    encode_preview(&small, format, enc.quality)
+}
+
+/// Encodes an already-sized preview; `Auto` must have been resolved by the caller.
+fn encode_preview(small: &image::DynamicImage, format: PreviewFormat, quality: u8) -> Result<(String, &'static str), tauri::Error> {
     let mut buf = Vec::new();
-    let mut cursor = Cursor::new(&mut buf);
-    small.write_to(&mut cursor, image::ImageFormat::Png).map_err(|e| ioerr(format!("png encode: {e}")))?;
-    Ok(base64::encode(buf))
+    let mime = match format {
+        PreviewFormat::Jpeg => {
+            // JPEG has no alpha channel
+            let rgb = small.to_rgb8();
+            image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buf, quality)
+                .encode_image(&rgb)
+                .map_err(|e| ioerr(format!("jpeg encode: {e}")))?;
+            "image/jpeg"
+        }
+        PreviewFormat::Webp => {
+            let small = if small.color().has_alpha() {
+                image::DynamicImage::ImageRgba8(small.to_rgba8())
+            } else {
+                image::DynamicImage::ImageRgb8(small.to_rgb8())
+            };
+            small.write_to(&mut Cursor::new(&mut buf), image::ImageFormat::WebP)
+                .map_err(|e| ioerr(format!("webp encode: {e}")))?;
+            "image/webp"
+        }
+        _ => {
+            small.write_to(&mut Cursor::new(&mut buf), image::ImageFormat::Png)
+                .map_err(|e| ioerr(format!("png encode: {e}")))?;
+            "image/png"
+        }
+    };
+    Ok((base64::encode(buf), mime))
+}
+
+/// An alpha channel alone doesn't count: many RGBA files (screenshots) are fully opaque.
+fn has_transparency(img: &image::DynamicImage) -> bool {
+    img.color().has_alpha() && img.to_rgba8().pixels().any(|p| p.0[3] < 255)
 }
 
-fn extract_video_keyframes_b64(path: &str, max_frames: usize) -> Result<Vec<String>, tauri::Error> {
-    let ffmpeg = match which::which("ffmpeg") {
-        Ok(p) => p,
-        Err(_) => return Ok(vec![]), // degrade quietly
+/// Frames are written losslessly by ffmpeg and then encoded like image previews (Auto
+/// means JPEG: frames are photographic and have no alpha). Returns the frames and their MIME type.
+fn extract_video_keyframes_b64(path: &str, max_frames: usize, width: u32, enc: PreviewEncoding) -> Result<(Vec<String>, &'static str), tauri::Error> {
+    let format = match enc.format {
+        PreviewFormat::Auto => PreviewFormat::Jpeg,
+        f => f,
+    };
+    let mut mime = "image/jpeg";
+    let Some(ffmpeg) = find_tool("ffmpeg") else {
+        return Ok((vec![], mime)); // degrade quietly
     };
-    let tmpdir = tempfile::tempdir().map_err(|e| ioerr(format!("tempdir: {e}")))?;
-    let pattern = tmpdir.path().join("kf-%02d.jpg");
+    let tmpdir = ScratchDir::new()?;
+    let pattern = tmpdir.path().join("kf-%02d.png");
 
-    // Grab ~1fps up to max_frames, scaled to ~512px width for sending
+    // Grab ~1fps up to max_frames, scaled to `width` for sending
     let frames_arg = max_frames.to_string();
-    let status = std::process::Command::new(ffmpeg)
-        .args(["-y","-i",path,"-vf","fps=1,scale=512:-1"])
-        .args(["-frames:v", &frames_arg])
-        .arg(pattern.to_string_lossy().to_string())
-        .status().map_err(|e| ioerr(format!("ffmpeg exec: {e}")))?;
-    if !status.success() { return Ok(vec![]); }
+    let filter = format!("fps=1,scale={width}:-1");
+    let _permit = ProcessPermit::acquire();
+    let output = output_with_timeout(
+        Command::new(ffmpeg)
+            .args(["-y","-i",path,"-vf",&filter])
+            .args(["-frames:v", &frames_arg])
+            .arg(pattern.to_string_lossy().to_string()),
+        "ffmpeg",
+    );
+    let status = match output {
+        Ok(output) => output.status,
+        Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+            log::warn!("[analyse {}] {path}: keyframe extraction: {e}", job::current());
+            return Ok((vec![], mime)); // like a failed decode: no frames
+        }
+        Err(e) => return Err(ioerr(format!("ffmpeg exec: {e}"))),
+    };
+    if !status.success() { return Ok((vec![], mime)); }
 
-    let mut frames = vec![];
+    let mut paths = vec![];
     for entry in fs::read_dir(tmpdir.path()).map_err(|e| ioerr(format!("readdir: {e}")))? {
         let p = entry.map_err(|e| ioerr(format!("dirent: {e}")))?.path();
-        if p.extension().and_then(|e| e.to_str()) == Some("jpg") {
-            let bytes = fs::read(&p).map_err(|e| ioerr(format!("read frame: {e}")))?;
-            frames.push(base64::encode(bytes));
-        }
+        if p.extension().and_then(|e| e.to_str()) == Some("png") { paths.push(p); }
+    }
+    paths.sort(); // kf-01, kf-02, ...: time order
+    paths.truncate(max_frames);
+
+    let mut frames = vec![];
+    for p in paths {
+        let img = image::open(&p).map_err(|e| ioerr(format!("read frame: {e}")))?;
+        let (b64, frame_mime) = encode_preview(&img, format, enc.quality)?;
+        frames.push(b64);
+        mime = frame_mime;
+    }
+    Ok((frames, mime))
+}
+
+/// `phash_b64` of the first keyframe, so re-encoded or rescaled copies of a clip hash alike.
+fn video_phash_b64(frames_b64: &[String]) -> Option<String> {
+    let bytes = base64::decode(frames_b64.first()?).ok()?;
+    let img = image::load_from_memory(&bytes).ok()?;
+    Some(phash_b64(&img))
+}
+
+/// Container duration alone: one `format=duration` query, without the stream listing or
+/// the decode check `enrich_video_ffprobe` does.
+fn probe_duration_sec(path: &str) -> Option<f64> {
+    let ff = find_tool("ffprobe")?;
+    let _permit = ProcessPermit::acquire();
+    let output = output_with_timeout(
+        Command::new(ff).args(["-v", "error", "-show_entries", "format=duration", "-of", "default=noprint_wrappers=1:nokey=1", path]),
+        "ffprobe",
+    ).map_err(|e| log::warn!("[analyse {}] ffprobe failed for {path}: {e}", job::current())).ok()?;
+    if !output.status.success() { return None; }
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+/// Filmstrip for scrubbing: `columns * rows` evenly spaced frames tiled into one JPEG.
+/// Returns an empty sheet (no image, no timestamps) when ffmpeg is unavailable.
+pub fn extract_video_sprite_sheet_b64(path: &str, columns: u32, rows: u32, tile_width: u32) -> Result<SpriteSheet, tauri::Error> {
+    let (columns, rows, tile_width) = (columns.max(1), rows.max(1), tile_width.max(16));
+    let mut sheet = SpriteSheet { columns, rows, tile_width, ..Default::default() };
+    let Some(ffmpeg) = find_tool("ffmpeg") else {
+        return Ok(sheet);
+    };
+    if let Some(msg) = temp_space_shortfall() {
+        return Err(ioerr(msg));
+    }
+
+    // Spread tiles over the whole clip when ffprobe can tell us how long it is.
+    let duration = probe_duration_sec(path).filter(|d| *d > 0.0);
+    let tiles = columns * rows;
+    let interval = duration.map_or(1.0, |d| d / tiles as f64);
+
+    let tmpdir = ScratchDir::new()?;
+    let out_jpg = tmpdir.path().join("sprite.jpg");
+    let filter = format!("fps={:.6},scale={tile_width}:-2,tile={columns}x{rows}", 1.0 / interval);
+    let _permit = ProcessPermit::acquire();
+    let output = output_with_timeout(
+        Command::new(ffmpeg)
+            .args(["-y","-i",path,"-vf",&filter,"-frames:v","1"])
+            .arg(out_jpg.to_string_lossy().to_string()),
+        "ffmpeg",
+    ).map_err(|e| match e.kind() {
+        std::io::ErrorKind::TimedOut => AnalyseError::VideoProbeError(e.to_string()).into(),
+        _ => ioerr(format!("ffmpeg exec: {e}")),
+    })?;
+    if !output.status.success() || !out_jpg.exists() { return Ok(sheet); }
+
+    let bytes = fs::read(&out_jpg).map_err(|e| ioerr(format!("read sprite: {e}")))?;
+    sheet.image_b64 = Some(base64::encode(bytes));
+    sheet.timestamps_sec = (0..tiles)
+        .map(|i| i as f64 * interval)
+        .take_while(|t| duration.map_or(true, |d| *t < d))
+        .collect();
+    Ok(sheet)
+}
+
+/// Effective resolution of the page-0 preview: its pixel size over the page size in inches.
+/// Longest side against longest side, so a /Rotate'd page still pairs up correctly.
+fn pdf_preview_dpi((w_px, h_px): (u32, u32), m: &MediaAnalysis) -> Option<f64> {
+    let (w_pt, h_pt) = (m.pdf.page0_width_pt?, m.pdf.page0_height_pt?);
+    let long_pt = w_pt.max(h_pt);
+    (long_pt > 0.0).then(|| f64::from(w_px.max(h_px)) / (long_pt / 72.0))
+}
+
+/// Base64, MIME type and the pixel size actually encoded.
+fn rasterize_pdf_page0_b64(path: &str, max_width: u32, enc: PreviewEncoding) -> Result<Option<(String, &'static str, (u32, u32))>, tauri::Error> {
+    // Try `pdftoppm`, then PDFium. If both are missing, return None (the AI can still use filename + numeric fields).
+    if let Some(img) = rasterize_pdf_page0_pdftoppm(path)? {
+        return encode_pdf_raster_b64(img, max_width, enc).map(Some);
+    }
+    match rasterize_pdf_page0_pdfium(path) {
+        Some(img) => encode_pdf_raster_b64(img, max_width, enc).map(Some),
+        None => Ok(None),
     }
-    frames.sort();
-    if frames.len() > max_frames { frames.truncate(max_frames); }
-    Ok(frames)
 }
 
-fn rasterize_pdf_page0_b64(path: &str) -> Result<Option<String>, tauri::Error> {
-    // Try `pdftoppm` if available. If missing, return None (the AI can still use filename + numeric fields).
-    let pdftoppm = match which::which("pdftoppm") {
-        Ok(p) => p,
-        Err(_) => return Ok(None),
+fn rasterize_pdf_page0_pdftoppm(path: &str) -> Result<Option<image::DynamicImage>, tauri::Error> {
+    let Some(pdftoppm) = find_tool("pdftoppm") else {
+        return Ok(None);
     };
-    let tmpdir = tempfile::tempdir().map_err(|e| ioerr(format!("tempdir: {e}")))?;
+    let tmpdir = ScratchDir::new()?;
     let prefix = tmpdir.path().join("p");
     let out_png = tmpdir.path().join("p-1.png");
 
-    let status = std::process::Command::new(pdftoppm)
-        .args(["-png","-f","1","-l","1"])
-        .arg(path)
-        .arg(prefix.to_string_lossy().to_string())
-        .status().map_err(|e| ioerr(format!("pdftoppm exec: {e}")))?;
-    if !status.success() || !out_png.exists() { return Ok(None); }
+    let _permit = ProcessPermit::acquire();
+    let output = output_with_timeout(
+        Command::new(pdftoppm)
+            .args(["-png","-f","1","-l","1"])
+            .arg(path)
+            .arg(prefix.to_string_lossy().to_string()),
+        "pdftoppm",
+    ).map_err(|e| ioerr(format!("pdftoppm exec: {e}")))?;
+    if !output.status.success() || !out_png.exists() { return Ok(None); }
 
     let img = image::open(&out_png).map_err(|e| ioerr(format!("open raster: {e}")))?;
+    Ok(Some(img))
+}
+
+// PDFium, bound once from the app directory or the system; None when neither has it.
+static PDFIUM: Lazy<Option<pdfium_render::prelude::Pdfium>> = Lazy::new(|| {
+    use pdfium_render::prelude::*;
+    Pdfium::bind_to_library(Pdfium::pdfium_platform_library_name_at_path("./"))
+        .or_else(|_| Pdfium::bind_to_system_library())
+        .map(Pdfium::new)
+        .ok()
+});
+
+/// Fallback for platforms without poppler: PDFium (see [`PDFIUM`]).
+/// Renders at pdftoppm's default 150 DPI so both paths hand the AI comparable pixels.
+fn rasterize_pdf_page0_pdfium(path: &str) -> Option<image::DynamicImage> {
+    use pdfium_render::prelude::*;
+
+    let pdfium = PDFIUM.as_ref()?;
+    let render = || -> Result<image::DynamicImage, PdfiumError> {
+        let doc = pdfium.load_pdf_from_file(path, None)?;
+        let page = doc.pages().get(0)?;
+        let config = PdfRenderConfig::new().scale_page_by_factor(150.0 / 72.0);
+        let bitmap = page.render_with_config(&config)?;
+        Ok(bitmap.as_image())
+    };
+    match render() {
+        Ok(img) => Some(img),
+        Err(e) => {
+            log::warn!("[analyse {}] pdfium render failed: {e}", job::current());
+            None
+        }
+    }
+}
+
+/// Pages are mostly text and line art, which JPEG smears, so Auto means PNG here.
+fn encode_pdf_raster_b64(img: image::DynamicImage, max_width: u32, enc: PreviewEncoding) -> Result<(String, &'static str, (u32, u32)), tauri::Error> {
     let (w, h) = img.dimensions();
-    let (nw, nh) = if w > 1400 { (1400u32, ((h as f32 * 1400.0 / w as f32).round() as u32).max(1)) } else { (w, h) };
+    let (nw, nh) = if w > max_width { (max_width, ((h as f32 * max_width as f32 / w as f32).round() as u32).max(1)) } else { (w, h) };
     let small = img.resize_exact(nw, nh, image::imageops::FilterType::CatmullRom);
+    let format = match enc.format {
+        PreviewFormat::Auto => PreviewFormat::Png,
+        f => f,
+    };
+    let (b64, mime) = encode_preview(&small, format, enc.quality)?;
+    Ok((b64, mime, (nw, nh)))
+}
 
-    let mut buf = Vec::new();
-    let mut cursor = Cursor::new(&mut buf);
-    small.write_to(&mut cursor, image::ImageFormat::Png).map_err(|e| ioerr(format!("png encode: {e}")))?;
-    Ok(Some(base64::encode(buf)))
+// -----------------------------------------------------------------------------
+// OCR (optional: `ocr` feature + tesseract on PATH)
+// -----------------------------------------------------------------------------
+
+/// Offline runs still build previews when there is an OCR pass to read them.
+const OCR_ENABLED: bool = cfg!(feature = "ocr");
+
+#[cfg(feature = "ocr")]
+const OCR_MAX_CHARS: usize = 2000;
+
+/// Reads text off the image / first-page previews so the AI sees more than a thumbnail.
+#[cfg(feature = "ocr")]
+fn enrich_ocr(out: &mut MediaAnalysis, previews: &MediaPreviews) {
+    let truncate = |t: String| t.chars().take(OCR_MAX_CHARS).collect::<String>();
+    if let Some(b64) = previews.image_b64.as_deref() {
+        out.image.ocr_text = ocr_preview_b64(b64, previews.image_mime.as_deref()).map(truncate);
+    }
+    if let Some(b64) = previews.pdf_page0_b64.as_deref() {
+        let text = ocr_preview_b64(b64, previews.pdf_page0_mime.as_deref());
+        // No text-layer counts (no text layer, or not Thorough): the OCR'd page is the best
+        // length signal there is.
+        if let (None, Some(t)) = (out.pdf.word_count, &text) {
+            let (words, chars) = text_counts(t);
+            out.pdf.word_count = Some(words);
+            out.pdf.char_count = Some(chars);
+        }
+        out.pdf.ocr_text = text.map(truncate);
+    }
+}
+
+#[cfg(not(feature = "ocr"))]
+fn enrich_ocr(_out: &mut MediaAnalysis, _previews: &MediaPreviews) {}
+
+#[cfg(feature = "ocr")]
+fn ocr_preview_b64(b64: &str, mime: Option<&str>) -> Option<String> {
+    let tesseract = find_tool("tesseract")?;
+    let bytes = base64::decode(b64).ok()?;
+    // Previews may be JPEG or WebP; tesseract goes by content, but keep the name honest.
+    let suffix = match mime {
+        Some("image/jpeg") => ".jpg",
+        Some("image/webp") => ".webp",
+        _ => ".png",
+    };
+    let tmp = tempfile::Builder::new().suffix(suffix).tempfile_in(&*TEMP_DIR).ok()?;
+    fs::write(tmp.path(), &bytes).ok()?;
+
+    let _permit = ProcessPermit::acquire();
+    let output = output_with_timeout(
+        Command::new(tesseract).arg(tmp.path()).args(["stdout", "--psm", "3"]),
+        "tesseract",
+    ).map_err(|e| log::warn!("[analyse {}] tesseract failed: {e}", job::current())).ok()?;
+    if !output.status.success() { return None; }
+
+    let text = String::from_utf8_lossy(&output.stdout).split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+// -----------------------------------------------------------------------------
+// Language detection (PDF text layer, OCR text, document text, email bodies)
+// -----------------------------------------------------------------------------
+
+/// Below this, whatlang's guesses are mostly noise.
+const LANGUAGE_MIN_CHARS: usize = 20;
+/// Documents and mail bodies are sampled from the start; more text rarely changes the answer.
+const LANGUAGE_SAMPLE_CHARS: usize = 4000;
+
+fn detect_language(text: &str, out: &mut MediaAnalysis) {
+    if text.trim().chars().count() <= LANGUAGE_MIN_CHARS { return; }
+    let Some(info) = whatlang::detect(text) else { return; };
+    if !info.is_reliable() { return; }
+    out.meta.detected_language = iso_639_1(info.lang()).map(str::to_string);
+}
+
+/// whatlang speaks ISO 639-3; the rest of the world mostly wants the two-letter code.
+fn iso_639_1(lang: whatlang::Lang) -> Option<&'static str> {
+    const CODES: &[(&str, &str)] = &[
+        ("epo", "eo"), ("eng", "en"), ("rus", "ru"), ("cmn", "zh"), ("spa", "es"), ("por", "pt"),
+        ("ita", "it"), ("ben", "bn"), ("fra", "fr"), ("deu", "de"), ("ukr", "uk"), ("kat", "ka"),
+        ("ara", "ar"), ("hin", "hi"), ("jpn", "ja"), ("heb", "he"), ("yid", "yi"), ("pol", "pl"),
+        ("amh", "am"), ("jav", "jv"), ("kor", "ko"), ("nob", "nb"), ("dan", "da"), ("swe", "sv"),
+        ("fin", "fi"), ("tur", "tr"), ("nld", "nl"), ("hun", "hu"), ("ces", "cs"), ("ell", "el"),
+        ("bul", "bg"), ("bel", "be"), ("mar", "mr"), ("kan", "kn"), ("ron", "ro"), ("slv", "sl"),
+        ("hrv", "hr"), ("srp", "sr"), ("mkd", "mk"), ("lit", "lt"), ("lav", "lv"), ("est", "et"),
+        ("tam", "ta"), ("vie", "vi"), ("urd", "ur"), ("tha", "th"), ("guj", "gu"), ("uzb", "uz"),
+        ("pan", "pa"), ("aze", "az"), ("ind", "id"), ("tel", "te"), ("pes", "fa"), ("mal", "ml"),
+        ("ori", "or"), ("mya", "my"), ("nep", "ne"), ("sin", "si"), ("khm", "km"), ("tuk", "tk"),
+        ("aka", "ak"), ("zul", "zu"), ("sna", "sn"), ("afr", "af"), ("lat", "la"), ("slk", "sk"),
+        ("cat", "ca"), ("tgl", "tl"), ("hye", "hy"),
+    ];
+    CODES.iter().find(|(three, _)| *three == lang.code()).map(|(_, two)| *two)
+}
+
+// -----------------------------------------------------------------------------
+// AI request pacing (process-wide token bucket + 429 backoff)
+// -----------------------------------------------------------------------------
+
+// Requests per second from TAGGER_AI_RPS (fractions allowed, e.g. 0.5); unset means unpaced.
+// Bursts up to one second's worth of requests.
+static AI_RATE_LIMITER: Lazy<Option<Mutex<TokenBucket>>> = Lazy::new(|| {
+    let rate = std::env::var("TAGGER_AI_RPS").ok()?.trim().parse::<f64>().ok().filter(|r| *r > 0.0)?;
+    let capacity = rate.max(1.0);
+    Some(Mutex::new(TokenBucket { rate, capacity, tokens: capacity, last: Instant::now() }))
+});
+
+struct TokenBucket { rate: f64, capacity: f64, tokens: f64, last: Instant }
+
+/// Blocks until a request may go out.
+fn ai_rate_limit() {
+    let Some(bucket) = AI_RATE_LIMITER.as_ref() else { return; };
+    loop {
+        let wait = {
+            let mut b = bucket.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            b.tokens = (b.tokens + now.duration_since(b.last).as_secs_f64() * b.rate).min(b.capacity);
+            b.last = now;
+            if b.tokens >= 1.0 {
+                b.tokens -= 1.0;
+                return;
+            }
+            (1.0 - b.tokens) / b.rate
+        };
+        std::thread::sleep(Duration::from_secs_f64(wait));
+    }
+}
+
+const AI_MAX_RETRIES: u32 = 3;
+const AI_MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// POSTs `body` through the rate limiter. On 429 waits for `Retry-After` (seconds form;
+/// otherwise 1s, 2s, 4s) and tries again, up to `AI_MAX_RETRIES` times. `build` is called
+/// once per attempt since a sent request can't be reused. None on any other failure.
+fn send_ai_request<B: Serialize>(
+    build: impl Fn() -> ureq::RequestBuilder<ureq::typestate::WithBody>,
+    body: &B,
+) -> Option<ureq::http::Response<ureq::Body>> {
+    for attempt in 0..=AI_MAX_RETRIES {
+        ai_rate_limit();
+        let res = build().config().http_status_as_error(false).build().send_json(body).ok()?;
+        if res.status() != 429 {
+            return res.status().is_success().then_some(res);
+        }
+        if attempt == AI_MAX_RETRIES { break; }
+        let wait = res.headers().get("retry-after")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(1 << attempt))
+            .min(AI_MAX_BACKOFF);
+        log::warn!("[analyse {}] AI endpoint rate-limited (429); retrying in {}s", job::current(), wait.as_secs());
+        std::thread::sleep(wait);
+    }
+    log::warn!("[analyse {}] AI endpoint still rate-limited after {AI_MAX_RETRIES} retries; giving up", job::current());
+    None
 }
 
 // -----------------------------------------------------------------------------
@@ -366,73 +3032,707 @@ struct AiTagIn<'a> {
     size_bytes: Option<u64>,
     created_at: Option<&'a str>,
     modified_at: Option<&'a str>,
+    // Capture time (EXIF DateTimeOriginal, else the video's recording date), RFC3339.
+    // Filesystem dates only say when the file was copied; prefer this for date-based renames.
+    date_taken: Option<String>,
     // Type & numeric facts (for context)
     file_type: &'a str,
     image_width: Option<u32>,
     image_height: Option<u32>,
+    image_sharpness: Option<f32>, // Laplacian variance; low means blurred / out of focus
+    image_noise_level: Option<f64>, // 0 clean .. 1 very noisy (high ISO)
+    image_page_count: Option<u32>, // multi-page TIFF (scans, faxes); the preview is page 1
+    image_dpi_x: Option<f64>, // from EXIF/JFIF; print size is width / dpi_x inches
+    image_dpi_y: Option<f64>,
+    // EXIF GPS, decimal degrees, for place tags; None with AnalyseOptions::redact_location.
+    gps_lat: Option<f64>,
+    gps_lon: Option<f64>,
     video_width: Option<u32>,
     video_height: Option<u32>,
     video_duration_sec: Option<f64>,
     video_fps: Option<f64>,
     video_codec: Option<&'a str>,
+    video_recorded_at: Option<&'a str>, // prefer over created/modified for date-based renames
+    video_is_hdr: bool,
+    // From the frame rate / recorded capture rate, so the model needn't guess from frames.
+    video_is_slow_motion: bool,
+    video_is_timelapse: bool,
+    video_chapter_titles: Vec<&'a str>,
+    // One entry per embedded subtitle track: the language code, or "unknown".
+    video_subtitle_languages: Vec<&'a str>,
     pdf_page_count: Option<u32>,
+    pdf_preview_dpi: Option<f64>, // resolution of pdf_page0_b64, to judge small print
+    svg_title: Option<&'a str>,
+    svg_description: Option<&'a str>,
+    office_app: Option<&'a str>,
+    office_title: Option<&'a str>,
+    office_author: Option<&'a str>,
+    office_word_count: Option<u32>,
+    office_slide_count: Option<u32>,
+    office_sheet_count: Option<u32>,
+    email_subject: Option<&'a str>,
+    email_from: Option<&'a str>,
+    email_date: Option<&'a str>,
+    email_attachment_count: u32,
     // Real media previews
     image_b64: Option<&'a str>,
+    image_mime: Option<&'a str>, // "image/jpeg" | "image/png" | "image/webp"
     video_frames_b64: Option<&'a [String]>,
+    video_frames_mime: Option<&'a str>,
     pdf_page0_b64: Option<&'a str>,
+    pdf_page0_mime: Option<&'a str>,
+    // Text read off the previews (OCR)
+    ocr_text: Option<&'a str>,
+    detected_language: Option<&'a str>, // ISO 639-1; answer in this language
+    pdf_text: Option<&'a str>, // Thorough profile only
+    barcodes: &'a [String],
     // Seed keywords
     raw_keywords: &'a [String],
+    // AnalyseOptions::instructions; chat providers get it in the system prompt instead.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instructions: Option<&'a str>,
 }
 
 #[derive(Deserialize)]
 struct AiTagOut {
-    #[serde(default)] tags: Option<Vec<String>>,
-    #[serde(default)] topics: Option<Vec<String>>,
+    // Each entry may be `{ "tag", "confidence" }` or a bare string (confidence 1.0).
+    #[serde(default)] tags: Option<Vec<TagWithConfidence>>,
+    #[serde(default)] topics: Option<Vec<TagWithConfidence>>,
     #[serde(default)] raw_keywords: Option<Vec<String>>,
     #[serde(default)] suggested: Option<Suggested>,
+    // Further candidates; pooled with `suggested` and ranked by confidence.
+    #[serde(default)] suggested_alternatives: Option<Vec<Suggested>>,
+    // Which of the configured endpoints answered (redacted); set after the call.
+    #[serde(skip)] served_by: Option<String>,
+}
+
+/// Sanitizes an AI rename/folder suggestion; `None` when nothing usable is left.
+fn clean_suggestion(mut s: Suggested) -> Option<Suggested> {
+    let rename = sanitize_filename(&s.rename);
+    if rename != s.rename {
+        log::debug!("[analyse {}] sanitized rename suggestion {:?} -> {:?}", job::current(), s.rename, rename);
+        s.rename = rename;
+    }
+    if !is_safe_relative_folder(&s.folder) {
+        log::warn!("[analyse {}] ignoring unsafe folder suggestion {:?}", job::current(), s.folder);
+        s.folder.clear();
+        s.folder_reason.clear();
+    }
+    s.alternatives.clear();
+    (!s.rename.is_empty() || !s.folder.is_empty()).then_some(s)
+}
+
+/// Pools the AI's `suggested` with its `suggested_alternatives`: the highest confidence
+/// wins and the rest become its `alternatives`. Stable sort, so ties keep the AI's order.
+fn ranked_suggestion(suggested: Option<Suggested>, alternatives: Option<Vec<Suggested>>) -> Option<Suggested> {
+    let mut candidates: Vec<Suggested> = suggested.into_iter()
+        .chain(alternatives.unwrap_or_default())
+        .filter_map(clean_suggestion)
+        .collect();
+    candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    if candidates.is_empty() { return None; }
+    let mut best = candidates.remove(0);
+    best.alternatives = candidates;
+    Some(best)
 }
 
 fn maybe_ai_enrichment(
-    file: &LoadedFile,
+    name: &str,
     m: &MediaAnalysis,
     raw_keywords: &Vec<String>,
     previews: &MediaPreviews,
+    opts: &AnalyseOptions,
 ) -> Option<AiTagOut> {
-    println!("a");
-    let endpoint = std::env::var("TAGGER_ENDPOINT").ok()?;
-    println!("{}", endpoint);
+    if job::current_cancelled() { return None; }
+    let enc = PreviewEncoding::from(opts);
+    let provider = AiProvider::from_env();
+    if AI_ENDPOINTS.is_empty() { return None; }
+    let endpoints = AI_ENDPOINTS.clone();
+
+    let budget = std::env::var("TAGGER_MAX_PAYLOAD_BYTES").ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .unwrap_or(AI_DEFAULT_MAX_PAYLOAD_BYTES);
+    let previews = fit_previews_to_budget(name, previews, budget, enc);
 
     let req = AiTagIn {
-        name: &file.name,
+        name,
         mime: m.meta.mime.as_deref(),
         size_bytes: m.meta.size_bytes,
         created_at: m.meta.created_at.as_deref(),
         modified_at: m.meta.modified_at.as_deref(),
-        file_type: &m.meta.file_type,
+        date_taken: date_taken(m),
+        file_type: m.meta.file_type.as_str(),
         image_width: m.image.width,
         image_height: m.image.height,
+        image_sharpness: m.image.sharpness,
+        image_noise_level: m.image.noise_level,
+        image_page_count: m.image.page_count,
+        image_dpi_x: m.image.dpi_x,
+        image_dpi_y: m.image.dpi_y,
+        gps_lat: m.image.gps_lat.filter(|_| !opts.redact_location),
+        gps_lon: m.image.gps_lon.filter(|_| !opts.redact_location),
         video_width: m.video.width,
         video_height: m.video.height,
         video_duration_sec: m.video.duration_sec,
         video_fps: m.video.fps,
         video_codec: m.video.codec.as_deref(),
+        video_recorded_at: m.video.recorded_at.as_deref(),
+        video_is_hdr: m.video.is_hdr,
+        video_is_slow_motion: m.video.is_slow_motion,
+        video_is_timelapse: m.video.is_timelapse,
+        video_chapter_titles: m.video.chapters.iter().map(|c| c.title.as_str()).filter(|t| !t.is_empty()).collect(),
+        video_subtitle_languages: m.video.subtitle_tracks.iter().map(|t| t.language.as_deref().unwrap_or("unknown")).collect(),
         pdf_page_count: m.pdf.page_count,
+        pdf_preview_dpi: m.pdf.preview_dpi,
+        svg_title: m.svg.title.as_deref(),
+        svg_description: m.svg.description.as_deref(),
+        office_app: m.office.app_name.as_deref(),
+        office_title: m.office.title.as_deref(),
+        office_author: m.office.author.as_deref(),
+        office_word_count: m.office.word_count,
+        office_slide_count: m.office.slide_count,
+        office_sheet_count: m.office.sheet_count,
+        email_subject: m.email.subject.as_deref(),
+        email_from: m.email.from.as_deref(),
+        email_date: m.email.date.as_deref(),
+        email_attachment_count: m.email.attachment_count,
         image_b64: previews.image_b64.as_deref(),
+        image_mime: previews.image_mime,
+        video_frames_b64: previews.video_frames_b64,
+        video_frames_mime: previews.video_frames_mime,
+        pdf_page0_b64: previews.pdf_page0_b64,
+        pdf_page0_mime: previews.pdf_page0_mime,
+        ocr_text: m.image.ocr_text.as_deref().or(m.pdf.ocr_text.as_deref()),
+        detected_language: m.meta.detected_language.as_deref(),
+        pdf_text: m.pdf.text_excerpt.as_deref(),
+        barcodes: &m.image.barcodes,
+        raw_keywords,
+        instructions: Some(opts.instructions.trim()).filter(|i| !i.is_empty()),
+    };
+
+    let send_one: Box<dyn Fn(&str) -> Option<AiTagOut> + Send> = match provider {
+        AiProvider::OpenAi => {
+            let chat = chat_request(&req)?;
+            Box::new(move |endpoint| openai_chat_enrichment(endpoint, &chat))
+        }
+        AiProvider::Anthropic => {
+            let chat = chat_request(&req)?;
+            Box::new(move |endpoint| anthropic_messages_enrichment(endpoint, &chat))
+        }
+        AiProvider::Ollama => {
+            let chat = chat_request(&req)?;
+            Box::new(move |endpoint| ollama_generate_enrichment(endpoint, &chat))
+        }
+        AiProvider::Endpoint => {
+            let body = serde_json::to_value(&req).ok()?;
+            Box::new(move |endpoint| {
+                let res = send_ai_request(|| ureq::post(endpoint), &body)?;
+                res.into_body().read_json::<AiTagOut>().ok()
+            })
+        }
+    };
+    let label = name.to_string();
+    let send = Box::new(move || {
+        for (i, endpoint) in endpoints.iter().enumerate() {
+            if i > 0 && job::current_cancelled() { return None; }
+            // Self-hosted endpoints sometimes carry a key in the query string or userinfo.
+            let shown = redact_url(endpoint);
+            log::debug!("[analyse {}] {label}: AI request to {shown}", job::current());
+            if let Some(mut out) = send_one(endpoint) {
+                out.served_by = Some(shown);
+                return Some(out);
+            }
+            if i + 1 < endpoints.len() {
+                log::warn!("[analyse {}] {label}: no usable answer from {shown}, trying the next endpoint", job::current());
+            }
+        }
+        None
+    });
+    run_abortable(name, send)
+}
+
+// The AI endpoints, read once so configuration problems are reported at startup rather
+// than for every file. Empty means AI enrichment is off.
+static AI_ENDPOINTS: Lazy<Vec<String>> = Lazy::new(|| {
+    let endpoints = ai_endpoints(AiProvider::from_env());
+    if endpoints.is_empty() {
+        log::info!("[analyse] TAGGER_ENDPOINT not set — AI enrichment disabled");
+    }
+    for e in endpoints.iter().filter(|e| !is_valid_endpoint(e)) {
+        log::warn!("[analyse] TAGGER_ENDPOINT: {} is not an http(s) URL; requests to it will fail", redact_url(e));
+    }
+    endpoints
+});
+
+fn is_valid_endpoint(url: &str) -> bool {
+    url.parse::<ureq::http::Uri>().is_ok_and(|u| {
+        matches!(u.scheme_str(), Some("http" | "https")) && u.host().is_some_and(|h| !h.is_empty())
+    })
+}
+
+/// `TAGGER_ENDPOINT`, tried in order until one answers: a comma-separated list or a JSON
+/// array of URLs. Unset or empty falls back to the provider's default, if it has one.
+fn ai_endpoints(provider: AiProvider) -> Vec<String> {
+    let raw = std::env::var("TAGGER_ENDPOINT").unwrap_or_default();
+    let raw = raw.trim();
+    let list: Vec<String> = if raw.starts_with('[') {
+        serde_json::from_str::<Vec<String>>(raw).unwrap_or_else(|e| {
+            log::warn!("[analyse] ignoring TAGGER_ENDPOINT: not a JSON array of URLs ({e})");
+            Vec::new()
+        })
+    } else {
+        raw.split(',').map(str::to_string).collect()
+    };
+    let list: Vec<String> = list.into_iter().map(|e| e.trim().to_string()).filter(|e| !e.is_empty()).collect();
+    if list.is_empty() { provider.default_endpoint().into_iter().collect() } else { list }
+}
+
+const AI_CANCEL_POLL: Duration = Duration::from_millis(200);
+
+/// Runs the AI exchange on its own thread, checking every 200 ms whether the job was
+/// cancelled. A blocking ureq call can't be interrupted, so a cancelled request is
+/// abandoned rather than stopped: its thread runs to completion on its own and the answer
+/// is dropped, while the analysis returns at once.
+fn run_abortable(name: &str, send: Box<dyn FnOnce() -> Option<AiTagOut> + Send>) -> Option<AiTagOut> {
+    use std::sync::mpsc::RecvTimeoutError;
+    let job_id = job::current_id();
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let _job = job_id.map(job::JobScope::enter);
+        let _ = tx.send(send());
+    });
+    loop {
+        match rx.recv_timeout(AI_CANCEL_POLL) {
+            Ok(out) => return out,
+            Err(RecvTimeoutError::Timeout) if job::current_cancelled() => {
+                log::info!("[analyse {}] {name}: cancelled, abandoning the AI request", job::current());
+                return None;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return None, // the request thread panicked
+        }
+    }
+}
+
+/// Scheme, host and path only; drops `user:pass@` and the query string.
+fn redact_url(url: &str) -> String {
+    let (scheme, rest) = url.split_once("://").unwrap_or(("", url));
+    let rest = rest.split(['?', '#']).next().unwrap_or("");
+    let rest = match rest.split_once('/') {
+        Some((authority, path)) => format!("{}/{path}", authority.rsplit('@').next().unwrap_or(authority)),
+        None => rest.rsplit('@').next().unwrap_or(rest).to_string(),
+    };
+    if scheme.is_empty() { rest } else { format!("{scheme}://{rest}") }
+}
+
+const AI_DEFAULT_MAX_PAYLOAD_BYTES: usize = 10 * 1024 * 1024;
+const AI_TRIMMED_MAX_FRAMES: usize = 2;
+const AI_TRIMMED_IMAGE_SIDE: u32 = 512;
+
+/// Previews as actually sent; borrowed unless the image had to be re-encoded smaller.
+struct SentPreviews<'a> {
+    image_b64: Option<std::borrow::Cow<'a, str>>,
+    image_mime: Option<&'a str>,
+    video_frames_b64: Option<&'a [String]>,
+    video_frames_mime: Option<&'a str>,
+    pdf_page0_b64: Option<&'a str>,
+    pdf_page0_mime: Option<&'a str>,
+}
+
+impl SentPreviews<'_> {
+    fn b64_len(&self) -> usize {
+        self.image_b64.as_ref().map_or(0, |s| s.len())
+            + self.video_frames_b64.map_or(0, |f| f.iter().map(String::len).sum())
+            + self.pdf_page0_b64.map_or(0, str::len)
+    }
+}
+
+/// Keeps the base64 previews under `budget` bytes: first at most two frames and a 512px
+/// image, then no previews at all.
+fn fit_previews_to_budget<'a>(name: &str, previews: &'a MediaPreviews, budget: usize, enc: PreviewEncoding) -> SentPreviews<'a> {
+    let mut sent = SentPreviews {
+        image_b64: previews.image_b64.as_deref().map(std::borrow::Cow::Borrowed),
+        image_mime: previews.image_mime.as_deref(),
         video_frames_b64: previews.video_frames_b64.as_deref(),
+        video_frames_mime: previews.video_frames_mime.as_deref(),
         pdf_page0_b64: previews.pdf_page0_b64.as_deref(),
-        raw_keywords,
+        pdf_page0_mime: previews.pdf_page0_mime.as_deref(),
+    };
+    let before = sent.b64_len();
+    if before <= budget { return sent; }
+
+    sent.video_frames_b64 = sent.video_frames_b64.map(|f| &f[..f.len().min(AI_TRIMMED_MAX_FRAMES)]);
+    let trimmed = sent.image_b64.and_then(|b64| {
+        let img = image::load_from_memory(&base64::decode(b64.as_bytes()).ok()?).ok()?;
+        downscale_image_b64(img, AI_TRIMMED_IMAGE_SIDE, enc).ok()
+    });
+    sent.image_mime = trimmed.as_ref().map(|(_, mime)| *mime);
+    sent.image_b64 = trimmed.map(|(b64, _)| std::borrow::Cow::Owned(b64));
+    if sent.b64_len() > budget {
+        sent = SentPreviews {
+            image_b64: None, image_mime: None, video_frames_b64: None, video_frames_mime: None,
+            pdf_page0_b64: None, pdf_page0_mime: None,
+        };
+        log::warn!("[analyse {}] {name}: previews ({before} bytes) exceed the {budget} byte AI payload limit; sending metadata only", job::current());
+    } else {
+        log::warn!("[analyse {}] {name}: previews ({before} bytes) exceed the {budget} byte AI payload limit; sending reduced previews", job::current());
+    }
+    sent
+}
+
+/// Request/response envelope used for the AI call, picked by `TAGGER_AI_PROVIDER`.
+#[derive(Clone, Copy)]
+enum AiProvider {
+    /// `AiTagIn` posted as-is to `TAGGER_ENDPOINT`, `AiTagOut` read straight back.
+    Endpoint,
+    OpenAi,
+    Anthropic,
+    /// Local model; media never leaves the machine.
+    Ollama,
+}
+
+impl AiProvider {
+    fn from_env() -> Self {
+        match std::env::var("TAGGER_AI_PROVIDER").unwrap_or_default().to_ascii_lowercase().as_str() {
+            "openai" => AiProvider::OpenAi,
+            "anthropic" => AiProvider::Anthropic,
+            "ollama" => AiProvider::Ollama,
+            _ => AiProvider::Endpoint,
+        }
+    }
+
+    fn default_endpoint(self) -> Option<String> {
+        match self {
+            AiProvider::Endpoint => None,
+            AiProvider::OpenAi => Some("https://api.openai.com/v1/chat/completions".to_string()),
+            AiProvider::Anthropic => Some("https://api.anthropic.com/v1/messages".to_string()),
+            AiProvider::Ollama => {
+                let host = std::env::var("TAGGER_OLLAMA_HOST").unwrap_or_else(|_| "http://localhost:11434".to_string());
+                Some(format!("{}/api/generate", host.trim_end_matches('/')))
+            }
+        }
+    }
+}
+
+const AI_SYSTEM_PROMPT: &str = "You tag media files. You receive the file's metadata as JSON plus \
+preview images. Reply with a single JSON object: {\"tags\": [{\"tag\": string, \"confidence\": number}], \
+\"topics\": [{\"tag\": string, \"confidence\": number}], \"raw_keywords\": [string], \
+\"suggested\": {\"rename\": string, \"reason\": string, \"confidence\": number, \
+\"folder\": string, \"folder_reason\": string}, \"suggested_alternatives\": [same shape as \"suggested\"]}. \
+\"suggested_alternatives\" is optional; use it for up to 3 other plausible names. \"folder\" is a relative directory such as \"2023/Nature/Scotland\". \
+For dates in names and folders, use \"date_taken\" when present, not \"created_at\" or \"modified_at\". \
+Confidences are between 0 and 1. Tags and keywords are lowercase.";
+
+/// AI_SYSTEM_PROMPT plus the caller's tagging instructions, if any. Ours come first, so the
+/// reply format still holds; the instructions shape the content.
+fn system_prompt(req: &AiTagIn) -> String {
+    match req.instructions {
+        Some(i) => format!("{AI_SYSTEM_PROMPT}\n\nAdditional instructions from the user: {i}"),
+        None => AI_SYSTEM_PROMPT.to_string(),
+    }
+}
+
+/// When the media was captured, as opposed to when the file was written.
+fn date_taken(m: &MediaAnalysis) -> Option<String> {
+    m.image.exif_datetime.as_deref().and_then(normalize_datetime)
+        .or_else(|| m.video.recorded_at.clone())
+}
+
+/// Same request reshaped for OpenAI's `/v1/chat/completions`: the metadata goes in as
+/// text and each preview as an `image_url` data URL.
+fn openai_chat_enrichment(endpoint: &str, req: &ChatRequest) -> Option<AiTagOut> {
+    let api_key = std::env::var("TAGGER_OPENAI_API_KEY").ok()?;
+    let model = std::env::var("TAGGER_OPENAI_MODEL").unwrap_or_else(|_| "gpt-4o".to_string());
+
+    let mut content = vec![serde_json::json!({ "type": "text", "text": req.meta })];
+    for (mime, b64) in &req.images {
+        content.push(serde_json::json!({
+            "type": "image_url",
+            "image_url": { "url": format!("data:{mime};base64,{b64}") },
+        }));
+    }
+
+    let body = serde_json::json!({
+        "model": model,
+        "response_format": { "type": "json_object" },
+        "messages": [
+            { "role": "system", "content": req.system },
+            { "role": "user", "content": content },
+        ],
+    });
+
+    let auth = format!("Bearer {api_key}");
+    let res = send_ai_request(|| ureq::post(endpoint).header("Authorization", &auth), &body)?;
+    let resp: serde_json::Value = res.into_body().read_json().ok()?;
+    let text = resp.pointer("/choices/0/message/content")?.as_str()?;
+    parse_ai_text(text)
+}
+
+/// Same request for Anthropic's `/v1/messages`: previews become base64 `image` blocks
+/// ahead of the metadata text.
+fn anthropic_messages_enrichment(endpoint: &str, req: &ChatRequest) -> Option<AiTagOut> {
+    let api_key = std::env::var("TAGGER_ANTHROPIC_API_KEY").ok()?;
+    let model = std::env::var("TAGGER_ANTHROPIC_MODEL").unwrap_or_else(|_| "claude-opus-4-5".to_string());
+    let max_tokens = std::env::var("TAGGER_AI_MAX_TOKENS").ok()
+        .and_then(|v| v.trim().parse::<u32>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(1024);
+
+    let mut content: Vec<serde_json::Value> = req.images.iter().map(|(mime, b64)| serde_json::json!({
+        "type": "image",
+        "source": { "type": "base64", "media_type": mime, "data": b64 },
+    })).collect();
+    content.push(serde_json::json!({ "type": "text", "text": req.meta }));
+
+    let body = serde_json::json!({
+        "model": model,
+        "max_tokens": max_tokens,
+        "system": req.system,
+        "messages": [{ "role": "user", "content": content }],
+    });
+
+    let res = send_ai_request(|| {
+        ureq::post(endpoint)
+            .header("x-api-key", &api_key)
+            .header("anthropic-version", "2023-06-01")
+    }, &body)?;
+    let resp: serde_json::Value = res.into_body().read_json().ok()?;
+    let text = resp.pointer("/content/0/text")?.as_str()?;
+    parse_ai_text(text)
+}
+
+/// Same request for Ollama's `/api/generate`. The reply streams as one JSON object per line;
+/// the `response` fragments are concatenated until `done`.
+fn ollama_generate_enrichment(endpoint: &str, req: &ChatRequest) -> Option<AiTagOut> {
+    use std::io::BufRead;
+    let model = std::env::var("TAGGER_OLLAMA_MODEL").unwrap_or_else(|_| "llava".to_string());
+
+    let images: Vec<&str> = req.images.iter().map(|(_, b64)| b64.as_str()).collect();
+    let body = serde_json::json!({
+        "model": model,
+        "system": req.system,
+        "prompt": req.meta,
+        "images": images,
+        "format": "json",
+        "stream": true,
+    });
+
+    let res = send_ai_request(|| ureq::post(endpoint), &body)?;
+    let reader = std::io::BufReader::new(res.into_body().into_reader());
+    let mut text = String::new();
+    for line in reader.lines() {
+        let line = line.ok()?;
+        if line.trim().is_empty() { continue; }
+        let chunk: serde_json::Value = serde_json::from_str(&line).ok()?;
+        if let Some(part) = chunk.get("response").and_then(|v| v.as_str()) {
+            text.push_str(part);
+        }
+        if chunk.get("done").and_then(|v| v.as_bool()) == Some(true) { break; }
+    }
+    parse_ai_text(&text)
+}
+
+/// What the chat providers send: the system prompt, the metadata JSON without the (large)
+/// base64 payloads, and those payloads as `(mime, b64)` so each provider can wrap them in
+/// its own image parts. Owned, since the exchange runs on a thread of its own.
+struct ChatRequest { system: String, meta: String, images: Vec<(String, String)> }
+
+fn chat_request(req: &AiTagIn) -> Option<ChatRequest> {
+    let mut meta = serde_json::to_value(req).ok()?;
+    if let Some(obj) = meta.as_object_mut() {
+        for k in ["image_b64", "image_mime", "video_frames_b64", "video_frames_mime", "pdf_page0_b64", "pdf_page0_mime", "instructions"] {
+            obj.remove(k);
+        }
+    }
+    let mut images = vec![];
+    let mut push = |mime: &str, b64: &str| images.push((mime.to_string(), b64.to_string()));
+    if let Some(b64) = req.image_b64 { push(req.image_mime.unwrap_or("image/png"), b64); }
+    for b64 in req.video_frames_b64.unwrap_or_default() {
+        push(req.video_frames_mime.unwrap_or("image/jpeg"), b64);
+    }
+    if let Some(b64) = req.pdf_page0_b64 { push(req.pdf_page0_mime.unwrap_or("image/png"), b64); }
+    Some(ChatRequest { system: system_prompt(req), meta: meta.to_string(), images })
+}
+
+/// Chat models sometimes wrap the JSON in a markdown fence; accept both.
+fn parse_ai_text(text: &str) -> Option<AiTagOut> {
+    let t = text.trim();
+    let t = t.strip_prefix("```json").or_else(|| t.strip_prefix("```")).unwrap_or(t);
+    let t = t.strip_suffix("```").unwrap_or(t);
+    serde_json::from_str::<AiTagOut>(t.trim()).ok()
+}
+
+// -----------------------------------------------------------------------------
+// Offline tagging (no network; deterministic but shallower than the AI)
+// -----------------------------------------------------------------------------
+
+const OFFLINE_MAX_KEYWORD_TAGS: usize = 8;
+
+fn offline_tags(m: &MediaAnalysis, raw_keywords: &[String]) -> (Vec<String>, Vec<String>) {
+    let mut tags: Vec<String> = vec![m.meta.file_type.as_str().to_string()];
+    let mut topics: Vec<String> = vec![];
+
+    let dims = match m.meta.file_type {
+        MetaFileType::Image => m.image.width.zip(m.image.height),
+        MetaFileType::Video => m.video.width.zip(m.video.height),
+        _ => None,
     };
+    if let Some((w, h)) = dims.filter(|&(w, h)| w > 0 && h > 0) {
+        tags.push(match w.cmp(&h) {
+            std::cmp::Ordering::Greater => "landscape",
+            std::cmp::Ordering::Less => "portrait",
+            std::cmp::Ordering::Equal => "square",
+        }.to_string());
+        let ratio = w.max(h) as f64 / w.min(h) as f64;
+        for (label, r) in [("16:9", 16.0 / 9.0), ("4:3", 4.0 / 3.0), ("3:2", 1.5), ("1:1", 1.0)] {
+            if (ratio - r).abs() < 0.02 { tags.push(label.to_string()); break; }
+        }
+        if w as u64 * h as u64 >= 8_000_000 { topics.push("hi_res_media".to_string()); }
+    }
+    if m.video.is_hdr { tags.push("hdr".to_string()); }
+    if m.image.is_screenshot == Some(true) {
+        tags.push("screenshot".to_string());
+        topics.push("screenshots".to_string());
+    } else if m.image.camera_make.is_some() || m.image.camera_model.is_some() {
+        topics.push("photography".to_string());
+    }
+    match m.meta.file_type {
+        MetaFileType::Pdf | MetaFileType::Office => topics.push("documents".to_string()),
+        MetaFileType::Email => topics.push("email".to_string()),
+        MetaFileType::Video => topics.push("video".to_string()),
+        _ => {}
+    }
 
-    println!("{}", endpoint);
+    // Keywords whose words recur across sources (filename, EXIF, outline, ...) rank first;
+    // ties keep their original order since sort_by is stable.
+    let mut freq: HashMap<&str, usize> = HashMap::new();
+    for k in raw_keywords {
+        for w in SPLIT_RE.split(k).filter(|w| !w.is_empty()) {
+            *freq.entry(w).or_default() += 1;
+        }
+    }
+    let score = |k: &String| SPLIT_RE.split(k).map(|w| freq.get(w).copied().unwrap_or(0)).max().unwrap_or(0);
+    let mut ranked: Vec<&String> = raw_keywords.iter().collect();
+    ranked.sort_by(|a, b| score(b).cmp(&score(a)));
+    for k in ranked.into_iter().take(OFFLINE_MAX_KEYWORD_TAGS) {
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(k)) { tags.push(k.clone()); }
+    }
 
-    let res = ureq::post(&endpoint).send_json(&req).ok()?;
-    res.into_body().read_json::<AiTagOut>().ok()
+    (tags, topics)
 }
 
 // -----------------------------------------------------------------------------
 // Errors
 // -----------------------------------------------------------------------------
 
-fn ioerr<S: Into<String>>(s: S) -> tauri::Error {
+pub(crate) fn ioerr<S: Into<String>>(s: S) -> tauri::Error {
     tauri::Error::from(std::io::Error::new(std::io::ErrorKind::Other, s.into()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_type_without_a_usable_extension_is_other() {
+        for name in ["README", ".hidden", "photo"] {
+            assert!(matches!(get_type(name), FileType::Other), "{name}");
+        }
+    }
+
+    #[test]
+    fn get_type_uses_the_last_extension() {
+        // `gz`, not `tar.gz`; neither is a media type.
+        assert!(matches!(get_type("a.tar.gz"), FileType::Other));
+        assert!(matches!(get_type("scan.tar.PDF"), FileType::Pdf));
+    }
+
+    /// A FIFO with no writer blocks any reader on open, like a stalled network share.
+    #[cfg(unix)]
+    #[test]
+    fn tool_reading_a_fifo_is_killed_at_the_deadline() {
+        let dir = tempfile::tempdir().unwrap();
+        let fifo = dir.path().join("stalled.mp4");
+        assert!(Command::new("mkfifo").arg(&fifo).status().unwrap().success());
+
+        let tool = find_tool("ffprobe").unwrap_or_else(|| PathBuf::from("cat"));
+        let started = Instant::now();
+        let err = output_within(Command::new(&tool).arg(&fifo), "probe", Duration::from_millis(300)).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+        assert_eq!(err.to_string(), "probe timeout");
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(
+            video_tool_error(err).downcast_ref::<AnalyseError>(),
+            Some(&AnalyseError::VideoProbeError("probe timeout".into())),
+        );
+    }
+
+    fn suggestion_from(response: &str) -> Option<Suggested> {
+        let ai: AiTagOut = serde_json::from_str(response).unwrap();
+        ranked_suggestion(ai.suggested, ai.suggested_alternatives)
+    }
+
+    fn renames(s: &[Suggested]) -> Vec<&str> {
+        s.iter().map(|s| s.rename.as_str()).collect()
+    }
+
+    #[test]
+    fn single_suggestion_has_no_alternatives() {
+        let s = suggestion_from(r#"{"suggested": {"rename": "beach.jpg", "reason": "sea", "confidence": 0.8}}"#).unwrap();
+        assert_eq!(s.rename, "beach.jpg");
+        assert!(s.alternatives.is_empty());
+    }
+
+    #[test]
+    fn alternatives_alone_are_ranked_by_confidence() {
+        let s = suggestion_from(r#"{"suggested_alternatives": [
+            {"rename": "low.jpg", "reason": "", "confidence": 0.4},
+            {"rename": "high.jpg", "reason": "", "confidence": 0.9},
+            {"rename": "mid.jpg", "reason": "", "confidence": 0.6}
+        ]}"#).unwrap();
+        assert_eq!(s.rename, "high.jpg");
+        assert_eq!(renames(&s.alternatives), ["mid.jpg", "low.jpg"]);
+    }
+
+    #[test]
+    fn suggested_and_alternatives_are_pooled() {
+        let s = suggestion_from(r#"{
+            "suggested": {"rename": "a.jpg", "reason": "", "confidence": 0.5},
+            "suggested_alternatives": [
+                {"rename": "b.jpg", "reason": "", "confidence": 0.9},
+                {"rename": "c.jpg", "reason": "", "confidence": 0.7}
+            ]
+        }"#).unwrap();
+        assert_eq!(s.rename, "b.jpg");
+        assert_eq!(renames(&s.alternatives), ["c.jpg", "a.jpg"]);
+        assert!(s.alternatives.iter().all(|a| a.alternatives.is_empty()));
+    }
+
+    #[test]
+    fn unsafe_suggestions_are_cleaned_or_dropped() {
+        let s = suggestion_from(r#"{
+            "suggested": {"rename": "ok.jpg", "reason": "", "confidence": 0.5,
+                          "folder": "../outside", "folder_reason": "escape"},
+            "suggested_alternatives": [
+                {"rename": "///", "reason": "", "confidence": 0.9, "folder": "/etc"},
+                {"rename": "a<b>.jpg", "reason": "", "confidence": 0.3}
+            ]
+        }"#).unwrap();
+        // Nothing usable is left of the 0.9 candidate.
+        assert_eq!(s.rename, "ok.jpg");
+        assert!(s.folder.is_empty() && s.folder_reason.is_empty());
+        assert_eq!(renames(&s.alternatives), ["ab.jpg"]);
+
+        assert!(suggestion_from(r#"{"suggested": {"rename": "..", "reason": "", "confidence": 1.0, "folder": "C:/x"}}"#).is_none());
+    }
+
+    #[test]
+    fn detected_language_is_two_letter_and_skips_short_text() {
+        let mut out = MediaAnalysis::default();
+        detect_language("Hallo Welt", &mut out);
+        assert_eq!(out.meta.detected_language, None);
+
+        detect_language("Sehr geehrte Damen und Herren, anbei finden Sie die Rechnung für den letzten Monat.", &mut out);
+        assert_eq!(out.meta.detected_language.as_deref(), Some("de"));
+    }
+}
@@ -0,0 +1,144 @@
+use std::path::Path;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::analyse::normalize_datetime;
+use crate::types::*;
+
+// -----------------------------------------------------------------------------
+// Burst sequences: runs of shots from one camera with (near) identical capture times
+// -----------------------------------------------------------------------------
+
+/// Largest gap between consecutive shots of one burst. EXIF DateTimeOriginal has whole
+/// seconds, so a burst spanning a second boundary shows up as a 1 s step.
+const BURST_MAX_GAP_SECS: i64 = 1;
+
+/// Groups of indices into `analyses`, each at least two images with the same camera
+/// make/model and capture times chained no more than a second apart. Images without an
+/// EXIF date or camera are never part of a burst. Groups are in capture order.
+pub fn detect_burst_sequences(analyses: &[MediaAnalysis]) -> Vec<Vec<usize>> {
+    let mut shots: Vec<(&str, &str, i64, usize)> = analyses.iter().enumerate()
+        .filter_map(|(i, a)| {
+            let make = a.image.camera_make.as_deref();
+            let model = a.image.camera_model.as_deref();
+            if make.is_none() && model.is_none() { return None; }
+            Some((make.unwrap_or_default(), model.unwrap_or_default(), capture_time(a)?, i))
+        })
+        .collect();
+    // Same camera together, then by time; the index keeps equal timestamps in file order.
+    shots.sort();
+
+    let mut bursts = vec![];
+    let mut run: Vec<usize> = vec![];
+    for (k, shot) in shots.iter().enumerate() {
+        let continues = k > 0 && {
+            let prev = &shots[k - 1];
+            (prev.0, prev.1) == (shot.0, shot.1) && shot.2 - prev.2 <= BURST_MAX_GAP_SECS
+        };
+        if !continues && run.len() > 1 { bursts.push(std::mem::take(&mut run)); }
+        if !continues { run.clear(); }
+        run.push(shot.3);
+    }
+    if run.len() > 1 { bursts.push(run); }
+    bursts.sort_by_key(|b| b[0]);
+    bursts
+}
+
+/// EXIF capture time as a Unix timestamp.
+fn capture_time(a: &MediaAnalysis) -> Option<i64> {
+    let taken = a.image.exif_datetime.as_deref()
+        .and_then(normalize_datetime)
+        .and_then(|d| OffsetDateTime::parse(&d, &Rfc3339).ok())?;
+    Some(taken.unix_timestamp())
+}
+
+/// The sharpest member of `burst` (Laplacian variance), falling back to the quality score
+/// when frames weren't decoded, and to the first frame when neither is known.
+pub fn select_best_burst_frame(analyses: &[MediaAnalysis], burst: &[usize]) -> Option<usize> {
+    let score = |i: usize| {
+        let a = &analyses[i];
+        (a.image.sharpness.unwrap_or(f32::MIN), a.meta.quality_score.unwrap_or(f32::MIN))
+    };
+    burst.iter().copied().reduce(|best, i| if score(i) > score(best) { i } else { best })
+}
+
+/// Marks `Image::is_burst_member` on every member and returns each burst with its best frame.
+pub fn detect_bursts_in(analyses: &mut [MediaAnalysis]) -> Vec<BurstSequence> {
+    let bursts = detect_burst_sequences(analyses);
+    for &i in bursts.iter().flatten() {
+        analyses[i].image.is_burst_member = true;
+    }
+    bursts.into_iter()
+        .map(|members| {
+            let best = select_best_burst_frame(analyses, &members).unwrap_or(members[0]);
+            BurstSequence { members, best }
+        })
+        .collect()
+}
+
+// -----------------------------------------------------------------------------
+// Photo sequences: numbered files (IMG_0001, IMG_0002, ...) shot seconds apart
+// -----------------------------------------------------------------------------
+
+/// Largest gap between consecutive shots of one sequence. Wider than a burst: bracketing
+/// and quick series without burst mode still fit.
+const SEQUENCE_MAX_GAP_SECS: i64 = 5;
+/// Largest step between consecutive file numbers, so a deleted frame or two doesn't split
+/// the sequence.
+const SEQUENCE_MAX_NUMBER_STEP: u64 = 3;
+
+// Counter at the end of the file stem: "IMG_0042" -> ("IMG_", 42), "DSC01234" -> ("DSC", 1234).
+static SEQUENCE_NAME_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(.*?)(\d{1,9})$").unwrap());
+
+/// Prefix and extension (both lowercased) plus the counter, for names that end in one.
+fn sequence_name(name: &str) -> Option<(String, String, u64)> {
+    let path = Path::new(name);
+    let stem = path.file_stem()?.to_str()?;
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+    let caps = SEQUENCE_NAME_RE.captures(stem)?;
+    Some((caps[1].to_lowercase(), ext, caps[2].parse().ok()?))
+}
+
+/// Groups of indices into `files`, each at least two images whose names share a prefix and
+/// extension with counters a few apart, shot no more than `SEQUENCE_MAX_GAP_SECS` apart in
+/// counter order. Images without an EXIF date or a numbered name are never grouped; a RAW
+/// and its JPEG twin land in separate groups. Groups are in counter order.
+pub fn detect_photo_sequences(files: &[AnalysedLoadedFile]) -> Vec<Vec<usize>> {
+    let mut shots: Vec<(String, String, u64, i64, usize)> = files.iter().enumerate()
+        .filter_map(|(i, f)| {
+            let (prefix, ext, number) = sequence_name(&f.name)?;
+            Some((prefix, ext, number, capture_time(&f.analysis)?, i))
+        })
+        .collect();
+    shots.sort();
+
+    let mut groups = vec![];
+    let mut run: Vec<usize> = vec![];
+    for (k, shot) in shots.iter().enumerate() {
+        let continues = k > 0 && {
+            let prev = &shots[k - 1];
+            (&prev.0, &prev.1) == (&shot.0, &shot.1)
+                && (1..=SEQUENCE_MAX_NUMBER_STEP).contains(&(shot.2 - prev.2))
+                && (shot.3 - prev.3).abs() <= SEQUENCE_MAX_GAP_SECS
+        };
+        if !continues && run.len() > 1 { groups.push(std::mem::take(&mut run)); }
+        if !continues { run.clear(); }
+        run.push(shot.4);
+    }
+    if run.len() > 1 { groups.push(run); }
+    groups.sort_by_key(|g| g[0]);
+    groups
+}
+
+/// Sets `Image::sequence_id` on every member of a sequence: the group's position in
+/// [`detect_photo_sequences`] order, so ids are stable for the same input.
+pub fn detect_sequences_in(files: &mut [AnalysedLoadedFile]) {
+    for (id, group) in detect_photo_sequences(files).into_iter().enumerate() {
+        for i in group {
+            files[i].analysis.image.sequence_id = Some(id as u32);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::time::UNIX_EPOCH;
+
+use tauri_plugin_store::StoreBuilder;
+
+use crate::analyse::ioerr;
+use crate::migrate::{migrate_analysis, ANALYSIS_SCHEMA_VERSION};
+use crate::types::*;
+
+// -----------------------------------------------------------------------------
+// Analysis cache (tauri_plugin_store), keyed by path + mtime
+// -----------------------------------------------------------------------------
+
+const CACHE_STORE: &str = "analysis-cache.json";
+
+// The cache can hold a whole library; skip the default pretty-printing.
+fn compact_json(
+    cache: &HashMap<String, serde_json::Value>,
+) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    Ok(serde_json::to_vec(cache)?)
+}
+
+fn open_store(app: &tauri::AppHandle) -> Result<std::sync::Arc<tauri_plugin_store::Store<tauri::Wry>>, tauri::Error> {
+    StoreBuilder::new(app, CACHE_STORE)
+        .serialize(compact_json)
+        .build()
+        .map_err(|e| ioerr(format!("analysis cache: {e}")))
+}
+
+fn key_prefix(path: &str) -> String {
+    format!("analysis:{path}@")
+}
+
+/// `analysis:{path}@{mtime_ms}`; None when the file can't be stat'ed.
+fn cache_key(path: &str) -> Option<String> {
+    let mtime = std::fs::metadata(path).ok()?.modified().ok()?;
+    let ms = mtime.duration_since(UNIX_EPOCH).ok()?.as_millis();
+    Some(format!("{}{ms}", key_prefix(path)))
+}
+
+/// Stores `analysis` for the file's current mtime and drops entries for older versions.
+/// Previews are never cached.
+pub fn put(app: &tauri::AppHandle, path: &str, analysis: &MediaAnalysis) -> Result<(), tauri::Error> {
+    let Some(key) = cache_key(path) else { return Ok(()); };
+    let store = open_store(app)?;
+    let mut value = serde_json::to_value(analysis).map_err(|e| ioerr(format!("analysis cache encode: {e}")))?;
+    if let Some(obj) = value.as_object_mut() { obj.remove("previews"); }
+
+    let prefix = key_prefix(path);
+    for old in store.keys().into_iter().filter(|k| k.starts_with(&prefix) && *k != key) {
+        store.delete(old);
+    }
+    store.set(key, value);
+    Ok(())
+}
+
+/// The cached analysis for `path` (upgraded if an older build stored it), unless the file
+/// changed since it was stored.
+pub fn get(app: &tauri::AppHandle, path: &str) -> Result<Option<MediaAnalysis>, tauri::Error> {
+    let Some(key) = cache_key(path) else { return Ok(None); };
+    let store = open_store(app)?;
+    let Some(value) = store.get(key) else { return Ok(None); };
+    match migrate_analysis(value, ANALYSIS_SCHEMA_VERSION) {
+        Ok(analysis) => Ok(Some(analysis)),
+        Err(e) => {
+            log::warn!("[cache] ignoring unreadable entry for {path}: {e}");
+            Ok(None)
+        }
+    }
+}
+
+/// Removes every cached version for `path`; returns whether anything was removed.
+pub fn clear(app: &tauri::AppHandle, path: &str) -> Result<bool, tauri::Error> {
+    let store = open_store(app)?;
+    let prefix = key_prefix(path);
+    let mut removed = false;
+    for k in store.keys().into_iter().filter(|k| k.starts_with(&prefix)) {
+        removed |= store.delete(k);
+    }
+    Ok(removed)
+}
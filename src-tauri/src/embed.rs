@@ -0,0 +1,294 @@
+use std::io::{Cursor, Write};
+use std::path::Path;
+
+use crate::analyse::{apply_embedded_keywords, ioerr, xml_unescape, XMP_LI_RE, XMP_PACKET_RE, XMP_SUBJECT_RE};
+use crate::privacy::PNG_SIGNATURE;
+use crate::types::*;
+
+// -----------------------------------------------------------------------------
+// Writing tags into image metadata (XMP dc:subject, IPTC 2:25); pixels are not touched
+// -----------------------------------------------------------------------------
+
+const XMP_APP1_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const PHOTOSHOP_APP13_HEADER: &[u8] = b"Photoshop 3.0\0";
+const PNG_XMP_KEYWORD: &[u8] = b"XML:com.adobe.xmp";
+/// A JPEG segment's u16 length counts its own two bytes.
+const JPEG_SEGMENT_MAX_PAYLOAD: usize = u16::MAX as usize - 2;
+/// IIM caps dataset 2:25 (Keywords) at 64 bytes.
+const IPTC_KEYWORD_MAX_BYTES: usize = 64;
+/// IIM 1:90 value declaring UTF-8.
+const IPTC_UTF8: &[u8] = b"\x1b%G";
+
+/// Adds `tags` to each file's embedded keywords, keeping the ones already there. Files
+/// that can't hold them (anything but JPEG and PNG) or fail the read-back check are
+/// reported as skipped and left unchanged.
+pub fn write_image_tags_all(paths: &[String], tags: &[String]) -> Vec<TagWriteOutcome> {
+    let mut clean: Vec<String> = vec![];
+    for t in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        if !clean.iter().any(|c| c.eq_ignore_ascii_case(t)) { clean.push(t.to_string()); }
+    }
+    paths.iter()
+        .map(|path| match embed_image_tags(path, &clean) {
+            Ok(()) => TagWriteOutcome { path: path.clone(), written: true, skipped_reason: None },
+            Err(e) => {
+                log::warn!("[embed] {e}");
+                TagWriteOutcome { path: path.clone(), written: false, skipped_reason: Some(e.to_string()) }
+            }
+        })
+        .collect()
+}
+
+/// JPEGs get both XMP (APP1) and IPTC (APP13); PNGs only XMP (an iTXt chunk), as PNG has no
+/// IPTC block. The result is read back with the analysis code and must still parse as an
+/// image before it replaces the original.
+pub fn embed_image_tags(path: &str, tags: &[String]) -> Result<(), tauri::Error> {
+    let data = std::fs::read(path).map_err(|e| ioerr(format!("read {path}: {e}")))?;
+    let tagged = if data.starts_with(&[0xFF, 0xD8]) {
+        embed_jpeg(&data, tags)
+    } else if data.starts_with(PNG_SIGNATURE) {
+        embed_png(&data, tags)
+    } else {
+        return Err(ioerr(format!("{path}: only JPEG and PNG can hold embedded tags")));
+    }
+    .map_err(|e| ioerr(format!("{path}: {e}")))?;
+
+    let mut check = MediaAnalysis::default();
+    apply_embedded_keywords(&tagged, &mut check);
+    if let Some(missing) = tags.iter().find(|t| !check.tagging.raw_keywords.contains(&t.to_lowercase())) {
+        return Err(ioerr(format!("{path}: tag {missing:?} missing after writing")));
+    }
+    image::ImageReader::new(Cursor::new(&tagged))
+        .with_guessed_format()
+        .map_err(image::ImageError::IoError)
+        .and_then(|r| r.into_dimensions())
+        .map_err(|e| ioerr(format!("{path}: image unreadable after writing: {e}")))?;
+
+    let dir = Path::new(path).parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+    tmp.write_all(&tagged)?;
+    tmp.flush()?;
+    tmp.persist(path).map_err(|e| ioerr(format!("write {path}: {}", e.error)))?;
+    Ok(())
+}
+
+// ---- XMP
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn xmp_subject_bag(keywords: &[String]) -> String {
+    let items: String = keywords.iter().map(|k| format!("<rdf:li>{}</rdf:li>", xml_escape(k))).collect();
+    format!("<dc:subject><rdf:Bag>{items}</rdf:Bag></dc:subject>")
+}
+
+/// Merges `tags` into the packet's dc:subject bag, adding one if there is none. Everything
+/// else in an existing packet (ratings, GPano, edit history) is kept byte for byte.
+fn merge_xmp(packet: Option<&[u8]>, tags: &[String]) -> Vec<u8> {
+    let description = |bag: String| {
+        format!(r#"<rdf:Description rdf:about="" xmlns:dc="http://purl.org/dc/elements/1.1/">{bag}</rdf:Description>"#)
+    };
+    let existing = packet
+        .and_then(|p| XMP_PACKET_RE.find(p).map(|m| (p, m.range())))
+        .and_then(|(p, r)| std::str::from_utf8(&p[r.clone()]).ok().map(|meta| (p, r, meta)));
+    let Some((packet, range, meta)) = existing else {
+        return format!(
+            "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\
+             <x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\
+             <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">{}</rdf:RDF>\
+             </x:xmpmeta><?xpacket end=\"w\"?>",
+            description(xmp_subject_bag(tags)),
+        )
+        .into_bytes();
+    };
+
+    let merged = if let Some(block) = XMP_SUBJECT_RE.captures(meta) {
+        let mut keywords: Vec<String> = XMP_LI_RE.captures_iter(&block[1]).map(|li| xml_unescape(&li[1])).collect();
+        for t in tags {
+            if !keywords.iter().any(|k| k.eq_ignore_ascii_case(t)) { keywords.push(t.clone()); }
+        }
+        let span = block.get(0).map_or(0..0, |m| m.range());
+        format!("{}{}{}", &meta[..span.start], xmp_subject_bag(&keywords), &meta[span.end..])
+    } else if let Some(end) = meta.find("</rdf:RDF>") {
+        format!("{}{}{}", &meta[..end], description(xmp_subject_bag(tags)), &meta[end..])
+    } else {
+        return merge_xmp(None, tags);
+    };
+    [&packet[..range.start], merged.as_bytes(), &packet[range.end..]].concat()
+}
+
+// ---- JPEG
+
+/// Marker segments before the scan, and the rest of the file from SOS (or EOI) on.
+fn jpeg_segments(data: &[u8]) -> Result<(Vec<&[u8]>, &[u8]), &'static str> {
+    let mut segments = vec![];
+    let mut i = 2;
+    loop {
+        if i + 1 >= data.len() || data[i] != 0xFF {
+            return Err("corrupt JPEG marker");
+        }
+        match data[i + 1] {
+            0xFF => { i += 1; continue; } // fill byte
+            0xD9 | 0xDA => return Ok((segments, &data[i..])), // EOI, SOS
+            0x01 | 0xD0..=0xD7 => { segments.push(&data[i..i + 2]); i += 2; continue; } // no length
+            _ => {}
+        }
+        if i + 3 >= data.len() {
+            return Err("truncated JPEG segment");
+        }
+        let end = i + 2 + u16::from_be_bytes([data[i + 2], data[i + 3]]) as usize;
+        if end > data.len() {
+            return Err("truncated JPEG segment");
+        }
+        segments.push(&data[i..end]);
+        i = end;
+    }
+}
+
+fn jpeg_segment(marker: u8, header: &[u8], body: &[u8]) -> Result<Vec<u8>, &'static str> {
+    let len = header.len() + body.len();
+    if len > JPEG_SEGMENT_MAX_PAYLOAD {
+        return Err("tags do not fit in one JPEG segment");
+    }
+    Ok([&[0xFF, marker][..], &((len + 2) as u16).to_be_bytes(), header, body].concat())
+}
+
+/// The payload after `header` when `seg` is an APPn segment of that kind.
+fn app_payload<'a>(seg: &'a [u8], marker: u8, header: &[u8]) -> Option<&'a [u8]> {
+    (seg.len() >= 4 && seg[1] == marker && seg[4..].starts_with(header)).then(|| &seg[4 + header.len()..])
+}
+
+fn embed_jpeg(data: &[u8], tags: &[String]) -> Result<Vec<u8>, &'static str> {
+    let (segments, scan) = jpeg_segments(data)?;
+    let xmp = merge_xmp(segments.iter().find_map(|s| app_payload(s, 0xE1, XMP_APP1_HEADER)), tags);
+    let irb = merge_photoshop_iptc(segments.iter().find_map(|s| app_payload(s, 0xED, PHOTOSHOP_APP13_HEADER)), tags)?;
+    let new_segments = [jpeg_segment(0xE1, XMP_APP1_HEADER, &xmp)?, jpeg_segment(0xED, PHOTOSHOP_APP13_HEADER, &irb)?];
+
+    // JFIF/EXIF must stay first, so the new segments go right after the leading APPn run.
+    let kept: Vec<&[u8]> = segments.into_iter().filter(|s| app_payload(s, 0xE1, XMP_APP1_HEADER).is_none() && app_payload(s, 0xED, PHOTOSHOP_APP13_HEADER).is_none()).collect();
+    let insert_at = kept.iter().position(|s| !(0xE0..=0xEF).contains(&s[1])).unwrap_or(kept.len());
+    let mut out = Vec::with_capacity(data.len() + new_segments.iter().map(Vec::len).sum::<usize>());
+    out.extend_from_slice(&data[..2]);
+    for s in &kept[..insert_at] { out.extend_from_slice(s); }
+    for s in &new_segments { out.extend_from_slice(s); }
+    for s in &kept[insert_at..] { out.extend_from_slice(s); }
+    out.extend_from_slice(scan);
+    Ok(out)
+}
+
+// ---- IPTC (IIM inside the Photoshop image resource block)
+
+fn iim_dataset(record: u8, dataset: u8, value: &[u8]) -> Vec<u8> {
+    [&[0x1C, record, dataset][..], &(value.len() as u16).to_be_bytes(), value].concat()
+}
+
+/// Keeps every other 8BIM resource and IIM dataset; Keywords become the old ones plus
+/// `tags`, and the block is declared UTF-8.
+fn merge_photoshop_iptc(irb: Option<&[u8]>, tags: &[String]) -> Result<Vec<u8>, &'static str> {
+    let mut resources = vec![];
+    let mut datasets: Vec<(u8, u8, &[u8])> = vec![];
+    let mut rest = irb.unwrap_or_default();
+    while rest.starts_with(b"8BIM") {
+        let truncated = "truncated Photoshop resource";
+        let id = u16::from_be_bytes([*rest.get(4).ok_or(truncated)?, *rest.get(5).ok_or(truncated)?]);
+        // Pascal-string name, padded to an even length; data padded likewise.
+        let size_at = 6 + ((*rest.get(6).ok_or(truncated)? as usize + 2) & !1);
+        let size = u32::from_be_bytes(rest.get(size_at..size_at + 4).ok_or(truncated)?.try_into().unwrap()) as usize;
+        let block = rest.get(size_at + 4..size_at + 4 + size).ok_or(truncated)?;
+        let end = (size_at + 4 + size + (size & 1)).min(rest.len());
+        if id == 0x0404 {
+            let mut j = 0;
+            while j + 5 <= block.len() && block[j] == 0x1C {
+                let len = u16::from_be_bytes([block[j + 3], block[j + 4]]) as usize;
+                let Some(value) = block.get(j + 5..j + 5 + len) else { break };
+                datasets.push((block[j + 1], block[j + 2], value));
+                j += 5 + len;
+            }
+        } else {
+            resources.extend_from_slice(&rest[..end]);
+        }
+        rest = &rest[end..];
+    }
+
+    let mut keywords: Vec<Vec<u8>> = datasets.iter().filter(|d| (d.0, d.1) == (2, 25)).map(|d| d.2.to_vec()).collect();
+    for t in tags {
+        if keywords.iter().any(|k| String::from_utf8_lossy(k).eq_ignore_ascii_case(t)) { continue; }
+        let mut cut = t.len().min(IPTC_KEYWORD_MAX_BYTES);
+        while !t.is_char_boundary(cut) { cut -= 1; }
+        keywords.push(t.as_bytes()[..cut].to_vec());
+    }
+
+    // Record 1 before record 2, and 2:00 (record version) first within record 2.
+    let mut iim = iim_dataset(1, 90, IPTC_UTF8);
+    for &(record, dataset, value) in datasets.iter().filter(|d| d.0 == 1 && d.1 != 90) {
+        iim.extend(iim_dataset(record, dataset, value));
+    }
+    if !datasets.iter().any(|d| (d.0, d.1) == (2, 0)) {
+        iim.extend(iim_dataset(2, 0, &[0, 4]));
+    }
+    for &(record, dataset, value) in datasets.iter().filter(|d| d.0 != 1 && (d.0, d.1) != (2, 25)) {
+        iim.extend(iim_dataset(record, dataset, value));
+    }
+    for k in &keywords { iim.extend(iim_dataset(2, 25, k)); }
+
+    resources.extend_from_slice(b"8BIM\x04\x04\0\0");
+    resources.extend_from_slice(&(iim.len() as u32).to_be_bytes());
+    resources.extend_from_slice(&iim);
+    if iim.len() % 2 == 1 { resources.push(0); }
+    Ok(resources)
+}
+
+// ---- PNG
+
+fn png_chunk(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(kind);
+    crc.update(body);
+    [&(body.len() as u32).to_be_bytes()[..], kind, body, &crc.finalize().to_be_bytes()].concat()
+}
+
+/// The XMP text of an uncompressed `XML:com.adobe.xmp` iTXt chunk body.
+fn png_xmp_text(body: &[u8]) -> Option<Result<&[u8], &'static str>> {
+    let rest = body.strip_prefix(PNG_XMP_KEYWORD)?.strip_prefix(b"\0")?;
+    if rest.first() != Some(&0) {
+        return Some(Err("compressed XMP chunk is not supported"));
+    }
+    // compression flag, method, then the (empty) language and translated keyword
+    let mut parts = rest.get(2..)?.splitn(3, |&b| b == 0);
+    let (_, _, text) = (parts.next()?, parts.next()?, parts.next()?);
+    Some(Ok(text))
+}
+
+fn embed_png(data: &[u8], tags: &[String]) -> Result<Vec<u8>, &'static str> {
+    let mut chunks: Vec<(&[u8], &[u8])> = vec![]; // (type, whole chunk)
+    let mut old_xmp = None;
+    let mut i = PNG_SIGNATURE.len();
+    while i < data.len() {
+        if i + 8 > data.len() {
+            return Err("truncated PNG chunk");
+        }
+        let len = u32::from_be_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]) as usize;
+        let end = i + 12 + len;
+        if end > data.len() {
+            return Err("truncated PNG chunk");
+        }
+        let kind = &data[i + 4..i + 8];
+        match (kind, png_xmp_text(&data[i + 8..i + 8 + len])) {
+            (b"iTXt", Some(text)) => old_xmp = Some(text?),
+            _ => chunks.push((kind, &data[i..end])),
+        }
+        i = end;
+        if kind == b"IEND" { break; }
+    }
+
+    let xmp = merge_xmp(old_xmp, tags);
+    let body = [PNG_XMP_KEYWORD, b"\0\0\0\0\0", &xmp].concat();
+    let new_chunk = png_chunk(b"iTXt", &body);
+    let insert_at = chunks.iter().position(|(k, _)| *k == b"IDAT").ok_or("PNG has no image data")?;
+    let mut out = Vec::with_capacity(data.len() + new_chunk.len());
+    out.extend_from_slice(PNG_SIGNATURE);
+    for (_, c) in &chunks[..insert_at] { out.extend_from_slice(c); }
+    out.extend_from_slice(&new_chunk);
+    for (_, c) in &chunks[insert_at..] { out.extend_from_slice(c); }
+    Ok(out)
+}
@@ -0,0 +1,42 @@
+use std::fs::File;
+use std::io::Read;
+
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+use crate::analyse::ioerr;
+use crate::types::*;
+
+// -----------------------------------------------------------------------------
+// File integrity: recompute a checksum and compare it with a known one
+// -----------------------------------------------------------------------------
+
+const HASH_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Hashes `path` in 64 KiB chunks; the file is never held in memory.
+pub fn hash_file(path: &str, algorithm: HashAlgorithm) -> Result<String, tauri::Error> {
+    match algorithm {
+        HashAlgorithm::Sha256 => hash_with::<Sha256>(path),
+        HashAlgorithm::Md5 => hash_with::<Md5>(path),
+    }
+}
+
+fn hash_with<D: Digest>(path: &str) -> Result<String, tauri::Error> {
+    let mut file = File::open(path).map_err(|e| ioerr(format!("open {path}: {e}")))?;
+    let mut hasher = D::new();
+    let mut buf = vec![0u8; HASH_CHUNK_BYTES];
+    loop {
+        let n = file.read(&mut buf).map_err(|e| ioerr(format!("read {path}: {e}")))?;
+        if n == 0 { break; }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finalize().iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Compares against `expected` ignoring case and surrounding whitespace, as copied from
+/// a `sha256sum` listing or a download page.
+pub fn verify_file(path: &str, expected: &str, algorithm: HashAlgorithm) -> Result<IntegrityCheck, tauri::Error> {
+    let actual_hash = hash_file(path, algorithm)?;
+    let expected_hash = expected.trim().to_ascii_lowercase();
+    Ok(IntegrityCheck { matches: actual_hash == expected_hash, actual_hash, expected_hash })
+}
@@ -0,0 +1,81 @@
+use std::cell::Cell;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+// -----------------------------------------------------------------------------
+// Job ids: correlate log lines from one analyse_file batch (or watcher event)
+// -----------------------------------------------------------------------------
+
+static NEXT_JOB: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static CURRENT: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+pub fn next_id() -> u64 {
+    NEXT_JOB.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Tags log lines written on this thread with `id` until dropped. Analysis runs on one
+/// blocking thread per file, so entering the scope there covers everything it logs.
+pub struct JobScope(Option<u64>);
+
+impl JobScope {
+    pub fn enter(id: u64) -> Self {
+        JobScope(CURRENT.with(|c| c.replace(Some(id))))
+    }
+}
+
+impl Drop for JobScope {
+    fn drop(&mut self) {
+        CURRENT.with(|c| c.set(self.0));
+    }
+}
+
+/// The job entered on this thread, if any; lets helper threads re-enter it.
+pub fn current_id() -> Option<u64> {
+    CURRENT.with(Cell::get)
+}
+
+/// `job=7`, or `job=-` outside any scope (e.g. `analyse_single` called as a library).
+pub struct JobTag(Option<u64>);
+
+pub fn current() -> JobTag {
+    JobTag(CURRENT.with(Cell::get))
+}
+
+impl fmt::Display for JobTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(id) => write!(f, "job={id}"),
+            None => f.write_str("job=-"),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Cancellation: a stopped job skips the files it hasn't started and abandons AI requests
+// -----------------------------------------------------------------------------
+
+static CANCELLED: Mutex<Vec<u64>> = Mutex::new(Vec::new());
+
+/// Marks `id` as stopped. Files already past their AI call still finish.
+pub fn cancel(id: u64) {
+    let mut cancelled = CANCELLED.lock().unwrap_or_else(|e| e.into_inner());
+    if !cancelled.contains(&id) { cancelled.push(id); }
+}
+
+pub fn is_cancelled(id: u64) -> bool {
+    CANCELLED.lock().unwrap_or_else(|e| e.into_inner()).contains(&id)
+}
+
+/// Whether the job entered on this thread was stopped; false outside any scope.
+pub fn current_cancelled() -> bool {
+    current_id().is_some_and(is_cancelled)
+}
+
+/// Drops the flag once the job has wound down, so the list only holds live jobs.
+pub fn forget(id: u64) {
+    CANCELLED.lock().unwrap_or_else(|e| e.into_inner()).retain(|&c| c != id);
+}
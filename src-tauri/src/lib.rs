@@ -1,21 +1,498 @@
 mod analyse;
+mod burst;
+mod cache;
+mod embed;
+mod integrity;
+mod job;
+mod migrate;
+mod privacy;
+mod rename;
+mod scan;
+mod session;
+mod similar;
+mod summary;
 mod types;
+mod util;
+mod watch;
 
-use analyse::analyse_single;
+pub use analyse::{analyse_single, analyse_single_bytes, validate_image_file, ImageValidationError};
+use base64::Engine;
 use log::info;
-use types::{LoadedFile, MediaAnalysis};
+use once_cell::sync::Lazy;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tauri::Emitter;
+use tokio::sync::Semaphore;
+pub use burst::{detect_burst_sequences, detect_bursts_in, detect_photo_sequences, detect_sequences_in, select_best_burst_frame};
+pub use embed::{embed_image_tags, write_image_tags_all};
+pub use integrity::{hash_file, verify_file};
+pub use migrate::{migrate_analysis, ANALYSIS_SCHEMA_VERSION};
+pub use privacy::{privacy_reports, strip_exif_file};
+pub use rename::{disambiguate_suggested_renames, execute_renames, plan_renames};
+pub use scan::scan_directory;
+pub use session::{export_analyses, import_analyses};
+pub use similar::find_similar_media;
+pub use summary::summarize_analyses;
+pub use types::{
+    AnalyseOptions, AnalysedLoadedFile, AnalysisProfile, AnalysisSession, BatchSummary, BurstReport, BurstSequence, HashAlgorithm, IntegrityCheck, LoadedBytes, LoadedFile,
+    MediaAnalysis, PreviewFormat, PrivacyReport, RenameOutcome, RenamePlan, SimilarMatch, SpriteSheet, TagWriteOutcome,
+};
+pub use util::{sanitize_filename, validate_filename};
 
-#[tauri::command]
-async fn analyse_file(files: Vec<LoadedFile>) -> Vec<MediaAnalysis> {
-    info!("ANALYSE BEGIN");
-    let ana: Vec<MediaAnalysis> = files
+// Files analysed at once by the batch commands, from TAGGER_CONCURRENCY (default 4).
+static ANALYSIS_CONCURRENCY: Lazy<usize> =
+    Lazy::new(|| concurrency_from(std::env::var("TAGGER_CONCURRENCY").ok().as_deref()));
+
+/// Parses a TAGGER_CONCURRENCY value; unset or invalid (with a warning) means 4.
+fn concurrency_from(raw: Option<&str>) -> usize {
+    let Some(raw) = raw else { return 4; };
+    match raw.trim().parse::<usize>() {
+        Ok(n) if n >= 1 => n,
+        _ => {
+            log::warn!("[analyse] ignoring TAGGER_CONCURRENCY={raw:?}: expected an integer >= 1");
+            4
+        }
+    }
+}
+
+/// `analyse_single` under `job_id`, caching the result. Blocks; run off the async runtime.
+fn analyse_and_cache(
+    app: &tauri::AppHandle,
+    file: LoadedFile,
+    options: &AnalyseOptions,
+    job_id: u64,
+) -> Result<MediaAnalysis, tauri::Error> {
+    let _job = job::JobScope::enter(job_id);
+    let path = file.path.clone();
+    let analysis = analyse_single(file, options)?;
+    if let Err(e) = cache::put(app, &path, &analysis) {
+        log::warn!("[analyse job={job_id}] caching analysis for {path} failed: {e}");
+    }
+    Ok(analysis)
+}
+
+type BoundedHandle<T> = tauri::async_runtime::JoinHandle<Result<T, tauri::Error>>;
+
+/// Runs each task on the blocking pool, at most `limit` at once; the handles resolve in
+/// `tasks` order when awaited so.
+fn spawn_bounded<T, F>(limit: usize, tasks: Vec<F>) -> Vec<BoundedHandle<T>>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let limit = Arc::new(Semaphore::new(limit));
+    tasks
         .into_iter()
-        .map(|f| analyse_single(f).unwrap())
+        .map(|task| {
+            let limit = limit.clone();
+            tauri::async_runtime::spawn(async move {
+                let _permit = limit.acquire_owned().await.expect("analysis semaphore closed");
+                tauri::async_runtime::spawn_blocking(task).await
+            })
+        })
+        .collect()
+}
+
+/// Starts one analysis task per file, at most `TAGGER_CONCURRENCY` (and with them,
+/// concurrent AI requests) running at once. Results are cached as they finish.
+fn spawn_analyses(
+    app: &tauri::AppHandle,
+    files: Vec<LoadedFile>,
+    options: Arc<AnalyseOptions>,
+    job_id: u64,
+) -> Vec<BoundedHandle<Result<MediaAnalysis, tauri::Error>>> {
+    let tasks = files
+        .into_iter()
+        .map(|f| {
+            let (app, options) = (app.clone(), options.clone());
+            move || analyse_and_cache(&app, f, &options, job_id)
+        })
         .collect();
-    println!("ANALYSE END");
+    spawn_bounded(*ANALYSIS_CONCURRENCY, tasks)
+}
+
+/// A handle from `spawn_analyses`, awaited. A failed file comes back with only `error`
+/// set rather than failing the batch, which keeps results lined up with `files`.
+async fn analysis_or_error(
+    handle: BoundedHandle<Result<MediaAnalysis, tauri::Error>>,
+    path: &str,
+    job_id: u64,
+) -> MediaAnalysis {
+    let error = match handle.await {
+        Ok(Ok(Ok(analysis))) => return analysis,
+        Ok(Ok(Err(e))) => e.to_string(),
+        Ok(Err(e)) | Err(e) => format!("analysis task: {e}"),
+    };
+    log::warn!("[analyse job={job_id}] analysis failed for {path}: {error}");
+    MediaAnalysis { error: Some(error), ..Default::default() }
+}
+
+/// Runs file-bound work (hashing, decoding, directory walks, ffmpeg) on the blocking pool
+/// so an async command doesn't stall the runtime's worker threads.
+async fn off_runtime<T, F>(what: &str, f: F) -> Result<T, tauri::Error>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, tauri::Error> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| analyse::ioerr(format!("{what} task: {e}")))?
+}
+
+fn resolve_options(options: Option<AnalyseOptions>, profile: Option<AnalysisProfile>) -> AnalyseOptions {
+    let mut options = options.unwrap_or_default();
+    if let Some(profile) = profile {
+        options.profile = profile;
+    }
+    options
+}
+
+#[tauri::command]
+async fn analyse_file(
+    app: tauri::AppHandle,
+    files: Vec<LoadedFile>,
+    options: Option<AnalyseOptions>,
+    profile: Option<AnalysisProfile>,
+) -> Vec<MediaAnalysis> {
+    let job_id = job::next_id();
+    let started = std::time::Instant::now();
+    let options = resolve_options(options, profile);
+    info!("[analyse job={job_id}] analysing {} file(s), profile {:?}", files.len(), options.profile);
+    let paths: Vec<String> = files.iter().map(|f| f.path.clone()).collect();
+    let handles = spawn_analyses(&app, files, Arc::new(options), job_id);
+    let mut ana: Vec<MediaAnalysis> = Vec::with_capacity(handles.len());
+    for (h, path) in handles.into_iter().zip(&paths) {
+        ana.push(analysis_or_error(h, path, job_id).await);
+    }
+    disambiguate_suggested_renames(&paths, &mut ana);
+    info!("[analyse job={job_id}] done in {} ms", started.elapsed().as_millis());
     ana
 }
 
+/// Single-file `analyse_file` (drag-drop, re-analysis after an edit); a failure is returned
+/// as the error rather than in `MediaAnalysis::error`.
+#[tauri::command]
+async fn analyse_one(
+    app: tauri::AppHandle,
+    file: LoadedFile,
+    options: Option<AnalyseOptions>,
+    profile: Option<AnalysisProfile>,
+) -> Result<MediaAnalysis, tauri::Error> {
+    let job_id = job::next_id();
+    let options = resolve_options(options, profile);
+    info!("[analyse job={job_id}] analysing {}, profile {:?}", file.path, options.profile);
+    tauri::async_runtime::spawn_blocking(move || analyse_and_cache(&app, file, &options, job_id))
+        .await
+        .map_err(|e| analyse::ioerr(format!("analysis task: {e}")))?
+}
+
+/// `analyse_one` for contents with no stable path. Images are decoded in memory, other
+/// types go through a tempfile; nothing is cached, since there is no path to key it on.
+#[tauri::command]
+async fn analyse_bytes(
+    file: LoadedBytes,
+    options: Option<AnalyseOptions>,
+    profile: Option<AnalysisProfile>,
+) -> Result<MediaAnalysis, tauri::Error> {
+    let job_id = job::next_id();
+    let options = resolve_options(options, profile);
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(file.data_b64.trim())
+        .map_err(|e| analyse::ioerr(format!("{}: invalid base64: {e}", file.name)))?;
+    info!("[analyse job={job_id}] analysing {} ({} bytes), profile {:?}", file.name, data.len(), options.profile);
+    tauri::async_runtime::spawn_blocking(move || {
+        let _job = job::JobScope::enter(job_id);
+        analyse_single_bytes(&file.name, &data, &options)
+    })
+    .await
+    .map_err(|e| analyse::ioerr(format!("analysis task: {e}")))?
+}
+
+/// Payload of the `analysis_page` event.
+#[derive(serde::Serialize)]
+struct AnalysisPage {
+    page_index: usize,
+    total_pages: usize,
+    results: Vec<MediaAnalysis>,
+}
+
+/// `analyse_file` for batches too large for one IPC message. Every page of `page_size`
+/// results but the last is emitted as an `analysis_page` event, in order, as soon as it is
+/// complete; the last page is the return value. Rename clashes are only resolved within a
+/// page, since earlier pages have already been delivered.
+#[tauri::command]
+async fn analyse_file_paged(
+    app: tauri::AppHandle,
+    files: Vec<LoadedFile>,
+    page_size: usize,
+    options: Option<AnalyseOptions>,
+    profile: Option<AnalysisProfile>,
+) -> Result<Vec<MediaAnalysis>, tauri::Error> {
+    let job_id = job::next_id();
+    let started = std::time::Instant::now();
+    let options = resolve_options(options, profile);
+    let page_size = page_size.max(1);
+    let total_pages = files.len().div_ceil(page_size).max(1);
+    info!(
+        "[analyse job={job_id}] analysing {} file(s) in {total_pages} page(s), profile {:?}",
+        files.len(),
+        options.profile
+    );
+    let paths: Vec<String> = files.iter().map(|f| f.path.clone()).collect();
+    let mut handles = spawn_analyses(&app, files, Arc::new(options), job_id).into_iter();
+    for (page_index, page_paths) in paths.chunks(page_size).enumerate() {
+        let mut results = Vec::with_capacity(page_paths.len());
+        // Paths first: zip stops on them without pulling the next page's handle.
+        for (path, h) in page_paths.iter().zip(handles.by_ref()) {
+            results.push(analysis_or_error(h, path, job_id).await);
+        }
+        disambiguate_suggested_renames(page_paths, &mut results);
+        if page_index + 1 == total_pages {
+            info!("[analyse job={job_id}] done in {} ms", started.elapsed().as_millis());
+            return Ok(results);
+        }
+        app.emit("analysis_page", &AnalysisPage { page_index, total_pages, results })?;
+    }
+    Ok(vec![]) // empty batch
+}
+
+/// Payload of the `analyse://result` event; `index` is the file's position in the request.
+#[derive(serde::Serialize)]
+struct AnalysisResultEvent {
+    job_id: u64,
+    index: usize,
+    path: String,
+    analysis: Option<MediaAnalysis>,
+    error: Option<String>,
+}
+
+/// Payload of the `analyse://done` event, sent after the last `analyse://result`.
+#[derive(serde::Serialize)]
+struct AnalysisDoneEvent {
+    job_id: u64,
+    total: usize,
+    failed: usize,
+    elapsed_ms: u128,
+    // Stopped by `cancel_analysis`; files it skipped count as failed.
+    cancelled: bool,
+}
+
+/// `analyse_file` without the batch-sized return value: each result is emitted as an
+/// `analyse://result` event as soon as it finishes (completion order, not `files` order),
+/// then `analyse://done`. Returns the job id at once so events can be matched to the
+/// request. A failed file is reported in its event instead of failing the batch. Nothing
+/// is collected, so suggested renames are not disambiguated across the batch. The job id
+/// also goes to `cancel_analysis`.
+#[tauri::command]
+async fn analyse_file_streamed(
+    app: tauri::AppHandle,
+    files: Vec<LoadedFile>,
+    options: Option<AnalyseOptions>,
+    profile: Option<AnalysisProfile>,
+) -> u64 {
+    let job_id = job::next_id();
+    let started = std::time::Instant::now();
+    let options = Arc::new(resolve_options(options, profile));
+    let total = files.len();
+    info!("[analyse job={job_id}] streaming {total} file(s), profile {:?}", options.profile);
+
+    let limit = Arc::new(Semaphore::new(*ANALYSIS_CONCURRENCY));
+    let failed = Arc::new(AtomicUsize::new(0));
+    let handles: Vec<_> = files
+        .into_iter()
+        .enumerate()
+        .map(|(index, f)| {
+            let (app, options, limit, failed) = (app.clone(), options.clone(), limit.clone(), failed.clone());
+            tauri::async_runtime::spawn(async move {
+                let _permit = limit.acquire_owned().await.expect("analysis semaphore closed");
+                let path = f.path.clone();
+                let (analysis, error) = if job::is_cancelled(job_id) {
+                    (None, Some("analysis cancelled".to_string()))
+                } else {
+                    let task_app = app.clone();
+                    match tauri::async_runtime::spawn_blocking(move || analyse_and_cache(&task_app, f, &options, job_id)).await {
+                        Ok(Ok(a)) => (Some(a), None),
+                        Ok(Err(e)) => (None, Some(e.to_string())),
+                        Err(e) => (None, Some(format!("analysis task: {e}"))),
+                    }
+                };
+                if let Some(e) = &error {
+                    failed.fetch_add(1, Ordering::Relaxed);
+                    log::warn!("[analyse job={job_id}] analysis failed for {path}: {e}");
+                }
+                let event = AnalysisResultEvent { job_id, index, path, analysis, error };
+                if let Err(e) = app.emit("analyse://result", &event) {
+                    log::warn!("[analyse job={job_id}] emit failed for {}: {e}", event.path);
+                }
+            })
+        })
+        .collect();
+
+    tauri::async_runtime::spawn(async move {
+        for h in handles {
+            let _ = h.await;
+        }
+        let elapsed_ms = started.elapsed().as_millis();
+        let cancelled = job::is_cancelled(job_id);
+        job::forget(job_id);
+        info!("[analyse job={job_id}] {} in {elapsed_ms} ms", if cancelled { "cancelled" } else { "done" });
+        let done = AnalysisDoneEvent { job_id, total, failed: failed.load(Ordering::Relaxed), elapsed_ms, cancelled };
+        if let Err(e) = app.emit("analyse://done", &done) {
+            log::warn!("[analyse job={job_id}] emit failed for analyse://done: {e}");
+        }
+    });
+    job_id
+}
+
+/// Stops an `analyse_file_streamed` job: files not yet started are reported as cancelled,
+/// and in-flight AI requests are abandoned so their files finish with local data only.
+/// `analyse://done` still follows. Cancelling a finished job has no effect.
+#[tauri::command]
+fn cancel_analysis(job_id: u64) {
+    info!("[analyse job={job_id}] cancel requested");
+    job::cancel(job_id);
+}
+
+/// Runs ffprobe and ffmpeg to completion, so it goes to the blocking pool like the analyses.
+#[tauri::command]
+async fn extract_video_sprite_sheet(
+    path: String,
+    columns: u32,
+    rows: u32,
+    tile_width: u32,
+) -> Result<SpriteSheet, tauri::Error> {
+    off_runtime("sprite sheet", move || analyse::extract_video_sprite_sheet_b64(&path, columns, rows, tile_width)).await
+}
+
+/// Recomputes the file's checksum (default SHA-256) and compares it with `expected_hash`,
+/// e.g. after copying to another drive. Does not run any analysis.
+#[tauri::command]
+async fn verify_file_integrity(
+    path: String,
+    expected_hash: String,
+    algorithm: Option<HashAlgorithm>,
+) -> Result<IntegrityCheck, tauri::Error> {
+    off_runtime("integrity check", move || verify_file(&path, &expected_hash, algorithm.unwrap_or_default())).await
+}
+
+/// GPS and likely-person flags for already-analysed files, with what to do before
+/// sharing them. Pure post-processing: nothing is re-read or sent anywhere.
+#[tauri::command]
+fn detect_private_data(files: Vec<AnalysedLoadedFile>) -> Vec<PrivacyReport> {
+    privacy_reports(&files)
+}
+
+/// Copies a JPEG or PNG to `dest_path` (may equal `path`) with its EXIF/text metadata
+/// removed. Lossless: the image data is not re-encoded.
+#[tauri::command]
+async fn strip_exif(path: String, dest_path: String) -> Result<(), tauri::Error> {
+    off_runtime("strip exif", move || strip_exif_file(&path, &dest_path)).await
+}
+
+/// Groups burst shots (same camera, capture times a second or less apart) in a batch of
+/// analyses and picks the sharpest frame of each. Pure post-processing, like
+/// `detect_private_data`.
+#[tauri::command]
+fn detect_bursts(mut analyses: Vec<MediaAnalysis>) -> BurstReport {
+    let bursts = detect_bursts_in(&mut analyses);
+    BurstReport { analyses, bursts }
+}
+
+/// Tags numbered shots taken seconds apart (IMG_0041, IMG_0042, ...) with a shared
+/// `image.sequence_id`. Needs the file names, so it takes the loaded files rather than bare
+/// analyses; otherwise pure post-processing like `detect_bursts`.
+#[tauri::command]
+fn detect_sequences(mut files: Vec<AnalysedLoadedFile>) -> Vec<AnalysedLoadedFile> {
+    detect_sequences_in(&mut files);
+    files
+}
+
+/// Analyses that look like the image at `reference_path` (phash Hamming distance at most
+/// `max_distance`), closest first. Only the reference is read; the library's hashes come
+/// from `analyses`, which need the Thorough profile to have one.
+#[tauri::command]
+async fn find_similar(reference_path: String, analyses: Vec<MediaAnalysis>, max_distance: u32) -> Result<Vec<SimilarMatch>, tauri::Error> {
+    off_runtime("similarity search", move || find_similar_media(&reference_path, &analyses, max_distance)).await
+}
+
+/// Dashboard aggregates for a batch: counts per type, total size and video duration, the
+/// most common tags/topics, and how many files got AI tags.
+#[tauri::command]
+fn summarize(analyses: Vec<MediaAnalysis>) -> BatchSummary {
+    summarize_analyses(&analyses)
+}
+
+/// Adds `tags` to the XMP/IPTC keywords embedded in each JPEG or PNG, in place and without
+/// re-encoding. Other formats are reported as skipped.
+#[tauri::command]
+async fn write_image_tags(paths: Vec<String>, tags: Vec<String>) -> Result<Vec<TagWriteOutcome>, tauri::Error> {
+    off_runtime("tag write", move || Ok(write_image_tags_all(&paths, &tags))).await
+}
+
+/// Media files under `path`, minus whatever `.mediaignore` files exclude.
+#[tauri::command]
+async fn scan_media_directory(path: String) -> Result<Vec<LoadedFile>, tauri::Error> {
+    off_runtime("directory scan", move || scan_directory(&path)).await
+}
+
+/// Dry run: where each file would go under `template`; nothing is renamed.
+#[tauri::command]
+async fn plan_file_renames(files: Vec<AnalysedLoadedFile>, template: String) -> Result<Vec<RenamePlan>, tauri::Error> {
+    off_runtime("rename plan", move || plan_renames(&files, &template)).await
+}
+
+/// Applies a (possibly user-edited) plan from `plan_file_renames`; rolls back on failure.
+#[tauri::command]
+async fn execute_file_renames(plan: Vec<RenamePlan>) -> Result<Vec<RenameOutcome>, tauri::Error> {
+    off_runtime("rename", move || execute_renames(&plan)).await
+}
+
+/// Analysis stored by `analyse_file` for `path`; None if missing or the file changed since.
+#[tauri::command]
+async fn get_cached_analysis(app: tauri::AppHandle, path: String) -> Result<Option<MediaAnalysis>, tauri::Error> {
+    off_runtime("cache read", move || cache::get(&app, &path)).await
+}
+
+#[tauri::command]
+async fn clear_cached_analysis(app: tauri::AppHandle, path: String) -> Result<bool, tauri::Error> {
+    off_runtime("cache clear", move || cache::clear(&app, &path)).await
+}
+
+/// Saves the current scan to `path` so it can be reopened without re-analysing.
+#[tauri::command]
+async fn export_analysis_session(files: Vec<AnalysedLoadedFile>, path: String) -> Result<(), tauri::Error> {
+    off_runtime("session export", move || export_analyses(files, &path)).await
+}
+
+#[tauri::command]
+async fn import_analysis_session(path: String) -> Result<AnalysisSession, tauri::Error> {
+    off_runtime("session import", move || import_analyses(&path)).await
+}
+
+/// Watches `path` (recursively) and emits `file_analysed` for each new or changed media
+/// file. Replaces any watcher already running.
+#[tauri::command]
+async fn start_file_watcher(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, watch::WatcherState>,
+    path: String,
+    options: Option<AnalyseOptions>,
+) -> Result<(), tauri::Error> {
+    let previous = state.0.lock().unwrap().take();
+    if let Some(w) = previous { w.stop(); }
+    let watcher = watch::start_watcher(app, &path, options.unwrap_or_default())?;
+    *state.0.lock().unwrap() = Some(watcher);
+    info!("watching {path}");
+    Ok(())
+}
+
+#[tauri::command]
+async fn stop_file_watcher(state: tauri::State<'_, watch::WatcherState>) -> Result<(), tauri::Error> {
+    let current = state.0.lock().unwrap().take();
+    if let Some(w) = current { w.stop(); }
+    Ok(())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let _ = dotenvy::dotenv();
@@ -24,16 +501,87 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_store::Builder::new().build())
+        .manage(watch::WatcherState::default())
         .setup(|app| {
-            #[cfg(debug_assertions)]
-            app.handle().plugin(
-                tauri_plugin_log::Builder::default()
-                    .level(log::LevelFilter::Info)
-                    .build(),
-            )?;
+            // Release builds log too: analysis warnings are the only trace of skipped work.
+            let level = if cfg!(debug_assertions) { log::LevelFilter::Debug } else { log::LevelFilter::Info };
+            app.handle().plugin(tauri_plugin_log::Builder::default().level(level).build())?;
+            // After the logger, so bad env values are actually reported; dotenvy has
+            // already run, so .env settings are included.
+            analyse::load_env_config();
+            Lazy::force(&ANALYSIS_CONCURRENCY);
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![analyse_file])
+        .invoke_handler(tauri::generate_handler![
+            analyse_file,
+            analyse_one,
+            analyse_bytes,
+            analyse_file_paged,
+            analyse_file_streamed,
+            cancel_analysis,
+            extract_video_sprite_sheet,
+            verify_file_integrity,
+            detect_private_data,
+            detect_bursts,
+            detect_sequences,
+            find_similar,
+            summarize,
+            strip_exif,
+            write_image_tags,
+            scan_media_directory,
+            plan_file_renames,
+            execute_file_renames,
+            get_cached_analysis,
+            clear_cached_analysis,
+            export_analysis_session,
+            import_analysis_session,
+            start_file_watcher,
+            stop_file_watcher,
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn concurrency_setting_falls_back_to_four() {
+        assert_eq!(concurrency_from(None), 4);
+        assert_eq!(concurrency_from(Some(" 2 ")), 2);
+        for bad in ["0", "-1", "two", ""] {
+            assert_eq!(concurrency_from(Some(bad)), 4, "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn concurrency_of_one_runs_files_one_at_a_time() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let tasks: Vec<_> = (0..10)
+            .map(|i| {
+                let (in_flight, peak) = (in_flight.clone(), peak.clone());
+                move || {
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(Duration::from_millis(20));
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    i
+                }
+            })
+            .collect();
+        let handles = spawn_bounded(concurrency_from(Some("1")), tasks);
+        let done: Vec<usize> = tauri::async_runtime::block_on(async {
+            let mut done = vec![];
+            for h in handles {
+                done.push(h.await.unwrap().unwrap());
+            }
+            done
+        });
+
+        assert_eq!(done, (0..10).collect::<Vec<_>>());
+        assert_eq!(peak.load(Ordering::SeqCst), 1);
+    }
+}
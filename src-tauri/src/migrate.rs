@@ -0,0 +1,56 @@
+use serde_json::Value;
+
+use crate::analyse::ioerr;
+use crate::types::*;
+
+// -----------------------------------------------------------------------------
+// Stored analyses (cache, exports): upgrading results written by older builds
+// -----------------------------------------------------------------------------
+
+/// Written into every `MediaAnalysis`. Bump it when a field is renamed, removed or changes
+/// shape, and append the matching step to `MIGRATIONS`. Added fields need neither: missing
+/// keys are filled with their defaults on load.
+pub const ANALYSIS_SCHEMA_VERSION: u32 = 1;
+
+/// `MIGRATIONS[i]` upgrades a version `i + 1` document to version `i + 2`.
+static MIGRATIONS: &[fn(Value) -> Value] = &[];
+
+/// Upgrades a stored analysis step by step to `target_version` and decodes it. Documents
+/// without a `schema_version` predate the field and count as version 1.
+pub fn migrate_analysis(old: Value, target_version: u32) -> Result<MediaAnalysis, tauri::Error> {
+    let from = old.get("schema_version").and_then(Value::as_u64).map_or(1, |v| v.max(1) as u32);
+    if target_version > ANALYSIS_SCHEMA_VERSION {
+        return Err(ioerr(format!(
+            "analysis schema {target_version} requested, this build knows up to {ANALYSIS_SCHEMA_VERSION}"
+        )));
+    }
+    if from > target_version {
+        return Err(ioerr(format!("analysis schema {from} is newer than {target_version}; downgrades are not supported")));
+    }
+
+    let mut value = old;
+    for version in from..target_version {
+        value = MIGRATIONS[version as usize - 1](value);
+        if let Some(obj) = value.as_object_mut() {
+            obj.insert("schema_version".into(), Value::from(version + 1));
+        }
+    }
+
+    let defaults = serde_json::to_value(MediaAnalysis::default()).map_err(|e| ioerr(format!("analysis encode: {e}")))?;
+    fill_missing(&mut value, &defaults);
+    let mut analysis: MediaAnalysis =
+        serde_json::from_value(value).map_err(|e| ioerr(format!("analysis decode: {e}")))?;
+    analysis.schema_version = target_version;
+    Ok(analysis)
+}
+
+/// Copies keys `value` lacks from `defaults`, recursing into objects both have.
+fn fill_missing(value: &mut Value, defaults: &Value) {
+    let (Value::Object(obj), Value::Object(defs)) = (value, defaults) else { return; };
+    for (k, d) in defs {
+        match obj.get_mut(k) {
+            Some(v) => fill_missing(v, d),
+            None => { obj.insert(k.clone(), d.clone()); }
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::io::Write;
+use std::path::Path;
+
+use crate::analyse::ioerr;
+use crate::types::*;
+
+// -----------------------------------------------------------------------------
+// Privacy review of finished analyses (no I/O, no AI call)
+// -----------------------------------------------------------------------------
+
+/// Tag words that suggest a recognisable person is in the picture. Matched against the
+/// words of each tag/topic, so "young woman" and "Group Portrait" count.
+const PERSON_WORDS: &[&str] = &[
+    "person", "people", "face", "faces", "portrait", "selfie", "man", "woman", "men", "women",
+    "child", "children", "kid", "kids", "baby", "family", "crowd",
+];
+
+/// Flags location data and likely people in already-analysed files, e.g. before a batch
+/// is shared or published. The face hint is only as good as the AI tags it reads.
+pub fn privacy_reports(files: &[AnalysedLoadedFile]) -> Vec<PrivacyReport> {
+    files.iter().map(|f| privacy_report(&f.path, &f.analysis)).collect()
+}
+
+fn privacy_report(path: &str, a: &MediaAnalysis) -> PrivacyReport {
+    let has_gps = a.image.gps_lat.is_some() && a.image.gps_lon.is_some();
+    let has_face_hint = a.tagging.tags.iter().chain(&a.tagging.topics).any(|t| {
+        t.tag.split(|c: char| !c.is_alphanumeric()).any(|w| PERSON_WORDS.contains(&w.to_lowercase().as_str()))
+    });
+
+    let mut recommendations = vec![];
+    if has_gps {
+        recommendations.push("Strip GPS location before sharing".to_string());
+    }
+    if has_face_hint {
+        recommendations.push("Contains identifiable person; check consent before publishing".to_string());
+    }
+    PrivacyReport { path: path.to_string(), has_gps, has_face_hint, recommendations }
+}
+
+// -----------------------------------------------------------------------------
+// Metadata stripping: byte-level, the image data itself is copied untouched
+// -----------------------------------------------------------------------------
+
+pub(crate) const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+/// Text and EXIF chunks; everything needed to render (and ICC/gamma) is kept.
+const PNG_METADATA_CHUNKS: &[&[u8; 4]] = &[b"tEXt", b"iTXt", b"zTXt", b"eXIf"];
+
+/// Writes `path` to `dest_path` (which may be the same file) without EXIF: every APP1
+/// segment of a JPEG (EXIF and XMP), or the text/EXIF chunks of a PNG. The copy is written
+/// beside the destination, checked with rexif, and only then moved into place.
+pub fn strip_exif_file(path: &str, dest_path: &str) -> Result<(), tauri::Error> {
+    let data = std::fs::read(path).map_err(|e| ioerr(format!("read {path}: {e}")))?;
+    let stripped = if data.starts_with(&[0xFF, 0xD8]) {
+        strip_jpeg_app1(&data)
+    } else if data.starts_with(PNG_SIGNATURE) {
+        strip_png_text(&data)
+    } else {
+        return Err(ioerr(format!("{path}: only JPEG and PNG can be stripped")));
+    }
+    .map_err(|e| ioerr(format!("{path}: {e}")))?;
+
+    let dir = Path::new(dest_path).parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+    tmp.write_all(&stripped)?;
+    tmp.flush()?;
+    // rexif errors on files with no EXIF at all, which is the expected outcome.
+    if rexif::parse_file(tmp.path()).is_ok_and(|exif| !exif.entries.is_empty()) {
+        return Err(ioerr(format!("{path}: EXIF still present after stripping")));
+    }
+    tmp.persist(dest_path).map_err(|e| ioerr(format!("write {dest_path}: {}", e.error)))?;
+    Ok(())
+}
+
+/// Copies marker segments except APP1; from SOS on, the rest of the file is copied as is.
+fn strip_jpeg_app1(data: &[u8]) -> Result<Vec<u8>, &'static str> {
+    let mut out = Vec::with_capacity(data.len());
+    out.extend_from_slice(&data[..2]);
+    let mut i = 2;
+    loop {
+        if i + 1 >= data.len() || data[i] != 0xFF {
+            return Err("corrupt JPEG marker");
+        }
+        let marker = data[i + 1];
+        match marker {
+            0xFF => { i += 1; continue; } // fill byte
+            0xD9 => { out.extend_from_slice(&data[i..]); return Ok(out); } // EOI
+            0x01 | 0xD0..=0xD7 => { out.extend_from_slice(&data[i..i + 2]); i += 2; continue; } // no length
+            _ => {}
+        }
+        if i + 3 >= data.len() {
+            return Err("truncated JPEG segment");
+        }
+        let end = i + 2 + u16::from_be_bytes([data[i + 2], data[i + 3]]) as usize;
+        if end > data.len() {
+            return Err("truncated JPEG segment");
+        }
+        if marker == 0xDA {
+            out.extend_from_slice(&data[i..]); // SOS: scan data up to EOI
+            return Ok(out);
+        }
+        if marker != 0xE1 {
+            out.extend_from_slice(&data[i..end]);
+        }
+        i = end;
+    }
+}
+
+/// Length (4) + type (4) + data + CRC (4) per chunk; dropped chunks need no CRC fix-up.
+fn strip_png_text(data: &[u8]) -> Result<Vec<u8>, &'static str> {
+    let mut out = Vec::with_capacity(data.len());
+    out.extend_from_slice(PNG_SIGNATURE);
+    let mut i = PNG_SIGNATURE.len();
+    while i < data.len() {
+        if i + 8 > data.len() {
+            return Err("truncated PNG chunk");
+        }
+        let len = u32::from_be_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]) as usize;
+        let end = i + 12 + len;
+        if end > data.len() {
+            return Err("truncated PNG chunk");
+        }
+        let kind = &data[i + 4..i + 8];
+        if !PNG_METADATA_CHUNKS.iter().any(|c| c.as_slice() == kind) {
+            out.extend_from_slice(&data[i..end]);
+        }
+        i = end;
+        if kind == b"IEND" { break; }
+    }
+    Ok(out)
+}
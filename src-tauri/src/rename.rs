@@ -0,0 +1,245 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::analyse::{ioerr, normalize_datetime};
+use crate::types::*;
+use crate::util::validate_filename;
+
+// -----------------------------------------------------------------------------
+// Rename planning (dry run: nothing on disk is touched)
+// -----------------------------------------------------------------------------
+
+static TOKEN_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{([a-z_]+)\}").unwrap());
+static UNSAFE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"[/\\:*?"<>|\x00-\x1f]+"#).unwrap());
+static SEP_RUN_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"([ _.-])[ _.-]+").unwrap());
+
+const RENAME_TOKENS: &[&str] = &[
+    "name", "ext", "n", "type", "date", "created", "modified", "size_kb",
+    "width", "height", "camera", "camera_make", "camera_model",
+    "duration", "codec", "fps", "tag", "topic", "keyword", "suggested",
+];
+
+/// Applies `template` (e.g. `{date}_{topic}_{n}`) to every file and returns the resulting
+/// mapping. The original extension is always kept; `{n}` is the 1-based position in the batch.
+/// Targets that clash with each other or with an existing file are flagged, not resolved.
+pub fn plan_renames(files: &[AnalysedLoadedFile], template: &str) -> Result<Vec<RenamePlan>, tauri::Error> {
+    let unknown: Vec<&str> = TOKEN_RE.captures_iter(template)
+        .map(|c| c.get(1).unwrap().as_str())
+        .filter(|t| !RENAME_TOKENS.contains(t))
+        .collect();
+    if !unknown.is_empty() {
+        return Err(ioerr(format!("unknown rename token(s): {}", unknown.join(", "))));
+    }
+
+    let mut plans: Vec<RenamePlan> = files.iter().enumerate().map(|(i, f)| {
+        let path = Path::new(&f.path);
+        let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let ext = path.extension().map(|s| s.to_string_lossy().to_string());
+
+        let rendered = TOKEN_RE.replace_all(template, |c: &regex::Captures| {
+            token_value(&c[1], f, &stem, ext.as_deref(), i + 1)
+        });
+        let mut new_stem = sanitize_stem(&rendered);
+        if new_stem.is_empty() { new_stem = stem; }
+
+        let file_name = match &ext {
+            Some(ext) => format!("{new_stem}.{ext}"),
+            None => new_stem,
+        };
+        let new_path = path.with_file_name(file_name).to_string_lossy().to_string();
+        RenamePlan { old_path: f.path.clone(), new_path, collision: false }
+    }).collect();
+
+    // Case-insensitive, since the default filesystems on macOS and Windows are.
+    let mut targets: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, p) in plans.iter().enumerate() {
+        targets.entry(p.new_path.to_lowercase()).or_default().push(i);
+    }
+    let sources: Vec<String> = plans.iter().map(|p| p.old_path.to_lowercase()).collect();
+    for idxs in targets.values() {
+        let taken_on_disk = |i: usize| {
+            let p = &plans[i];
+            // A file that is itself being renamed away frees its name.
+            Path::new(&p.new_path).exists()
+                && !p.new_path.eq_ignore_ascii_case(&p.old_path)
+                && !sources.contains(&p.new_path.to_lowercase())
+        };
+        let clash = idxs.len() > 1 || taken_on_disk(idxs[0]);
+        if clash {
+            for &i in idxs { plans[i].collision = true; }
+        }
+    }
+    Ok(plans)
+}
+
+fn token_value(token: &str, f: &AnalysedLoadedFile, stem: &str, ext: Option<&str>, n: usize) -> String {
+    let a = &f.analysis;
+    let date_of = |s: &Option<String>| s.as_deref().and_then(normalize_datetime).map(|d| d[..10].to_string());
+    let first = |v: &[TagWithConfidence]| v.first().map(|t| t.tag.clone()).unwrap_or_default();
+    let opt = |v: Option<String>| v.unwrap_or_default();
+    match token {
+        "name" => stem.to_string(),
+        "ext" => ext.unwrap_or_default().to_string(),
+        "n" => n.to_string(),
+        "type" => a.meta.file_type.as_str().to_string(),
+        // Capture time first, then the filesystem.
+        "date" => opt(date_of(&a.image.exif_datetime)
+            .or_else(|| date_of(&a.video.recorded_at))
+            .or_else(|| date_of(&a.meta.created_at))
+            .or_else(|| date_of(&a.meta.modified_at))),
+        "created" => opt(date_of(&a.meta.created_at)),
+        "modified" => opt(date_of(&a.meta.modified_at)),
+        "size_kb" => opt(a.meta.size_bytes.map(|b| (b / 1024).to_string())),
+        "width" => opt(a.image.width.or(a.video.width).map(|w| w.to_string())),
+        "height" => opt(a.image.height.or(a.video.height).map(|h| h.to_string())),
+        "camera" => [a.image.camera_make.as_deref(), a.image.camera_model.as_deref()]
+            .into_iter().flatten().collect::<Vec<_>>().join(" "),
+        "camera_make" => opt(a.image.camera_make.clone()),
+        "camera_model" => opt(a.image.camera_model.clone()),
+        "duration" => opt(a.video.duration_sec.map(|d| format!("{}s", d.round() as u64))),
+        "codec" => opt(a.video.codec.clone()),
+        "fps" => opt(a.video.fps.map(|f| format!("{}fps", f.round() as u64))),
+        "tag" => first(&a.tagging.tags),
+        "topic" => first(&a.tagging.topics),
+        "keyword" => opt(a.tagging.raw_keywords.first().cloned()),
+        "suggested" => Path::new(&a.suggested.rename).file_stem()
+            .map(|s| s.to_string_lossy().to_string()).unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+/// Makes the AI's `suggested.rename` values unique across a batch. Files whose suggestions
+/// land on the same target (same folder, names equal ignoring case) are numbered in batch
+/// order: `beach.jpg`, `beach.jpg` -> `beach-1.jpg`, `beach-2.jpg`. Suffixes skip names that
+/// another file in the batch already has. `paths[i]` is the file behind `analyses[i]`.
+pub fn disambiguate_suggested_renames(paths: &[String], analyses: &mut [MediaAnalysis]) {
+    let target_key = |path: &str, folder: &str, rename: &str| {
+        let dir = if folder.is_empty() {
+            Path::new(path).parent().map(Path::to_path_buf).unwrap_or_default()
+        } else {
+            PathBuf::from(folder)
+        };
+        dir.join(rename).to_string_lossy().to_lowercase()
+    };
+
+    let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
+    for i in 0..analyses.len().min(paths.len()) {
+        let s = &analyses[i].suggested;
+        if s.rename.is_empty() { continue; }
+        groups.entry(target_key(&paths[i], &s.folder, &s.rename)).or_default().push(i);
+    }
+    let mut taken: HashSet<String> = groups.keys().cloned().collect();
+
+    let mut clashes: Vec<Vec<usize>> = groups.into_values().filter(|g| g.len() > 1).collect();
+    clashes.sort_by_key(|g| g[0]);
+    for group in clashes {
+        let mut n = 0;
+        for i in group {
+            let s = &analyses[i].suggested;
+            let p = Path::new(&s.rename);
+            let stem = p.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            let ext = p.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+            let candidate = loop {
+                n += 1;
+                let candidate = format!("{stem}-{n}{ext}");
+                if taken.insert(target_key(&paths[i], &s.folder, &candidate)) { break candidate; }
+            };
+            analyses[i].suggested.rename = candidate;
+        }
+    }
+}
+
+/// Strips path separators and reserved characters, and tidies the separator runs left
+/// behind by empty tokens (`2024-01-01__3` -> `2024-01-01_3`).
+fn sanitize_stem(s: &str) -> String {
+    let s = UNSAFE_RE.replace_all(s, "-");
+    let s = SEP_RUN_RE.replace_all(&s, "$1");
+    s.trim_matches(|c: char| c == ' ' || c == '_' || c == '-' || c == '.').to_string()
+}
+
+// -----------------------------------------------------------------------------
+// Rename execution (all or nothing, best effort)
+// -----------------------------------------------------------------------------
+
+/// Performs a plan from `plan_renames`. The whole plan is validated before anything moves;
+/// files then go through a temporary name in their directory so swaps and chains
+/// (`a -> b`, `b -> a`) never clash. If any step fails, completed moves are undone in reverse.
+pub fn execute_renames(plan: &[RenamePlan]) -> Result<Vec<RenameOutcome>, tauri::Error> {
+    validate_plan(plan)?;
+
+    let pending: Vec<usize> = (0..plan.len()).filter(|&i| plan[i].old_path != plan[i].new_path).collect();
+    let temp_of = |i: usize| {
+        let old = Path::new(&plan[i].old_path);
+        let name = old.file_name().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        old.with_file_name(format!(".{name}.renaming-{}-{i}", std::process::id()))
+    };
+
+    // Completed moves, as (from, to), for rollback.
+    let mut done: Vec<(PathBuf, PathBuf)> = vec![];
+    let mut failure: Option<(usize, String)> = None;
+    let steps = pending.iter().map(|&i| (i, PathBuf::from(&plan[i].old_path), temp_of(i)))
+        .chain(pending.iter().map(|&i| (i, temp_of(i), PathBuf::from(&plan[i].new_path))));
+    for (i, from, to) in steps {
+        // fs::rename silently replaces on Unix; never let it clobber something that appeared meanwhile.
+        if to.exists() {
+            failure = Some((i, format!("{} already exists", to.display())));
+            break;
+        }
+        match fs::rename(&from, &to) {
+            Ok(()) => done.push((from, to)),
+            Err(e) => {
+                failure = Some((i, format!("rename {} -> {}: {e}", from.display(), to.display())));
+                break;
+            }
+        }
+    }
+
+    let Some((failed, reason)) = failure else {
+        return Ok(plan.iter().map(|p| RenameOutcome {
+            old_path: p.old_path.clone(),
+            new_path: p.new_path.clone(),
+            ok: true,
+            error: None,
+        }).collect());
+    };
+
+    let mut rollback_errors = vec![];
+    for (from, to) in done.iter().rev() {
+        if let Err(e) = fs::rename(to, from) {
+            rollback_errors.push(format!("could not restore {}: {e}", from.display()));
+        }
+    }
+    Ok(plan.iter().enumerate().map(|(i, p)| {
+        let mut error = if i == failed { reason.clone() } else { "rolled back".to_string() };
+        if !rollback_errors.is_empty() {
+            error = format!("{error} ({})", rollback_errors.join("; "));
+        }
+        RenameOutcome { old_path: p.old_path.clone(), new_path: p.new_path.clone(), ok: false, error: Some(error) }
+    }).collect())
+}
+
+fn validate_plan(plan: &[RenamePlan]) -> Result<(), tauri::Error> {
+    let sources: Vec<String> = plan.iter().map(|p| p.old_path.to_lowercase()).collect();
+    let mut targets: HashMap<String, &str> = HashMap::new();
+    for p in plan {
+        if !Path::new(&p.old_path).exists() {
+            return Err(ioerr(format!("{} does not exist", p.old_path)));
+        }
+        if let Some(other) = targets.insert(p.new_path.to_lowercase(), &p.old_path) {
+            return Err(ioerr(format!("{} and {} would both become {}", other, p.old_path, p.new_path)));
+        }
+        if p.new_path != p.old_path {
+            let name = Path::new(&p.new_path).file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+            validate_filename(&name).map_err(|e| ioerr(format!("{}: {e}", p.old_path)))?;
+        }
+        let freed = sources.contains(&p.new_path.to_lowercase());
+        if Path::new(&p.new_path).exists() && !freed {
+            return Err(ioerr(format!("{} already exists", p.new_path)));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use std::path::Path;
+
+use ignore::WalkBuilder;
+
+use crate::analyse::{get_type, ioerr};
+use crate::types::*;
+
+// -----------------------------------------------------------------------------
+// Directory scan (honours .mediaignore)
+// -----------------------------------------------------------------------------
+
+/// Per-directory ignore file, gitignore syntax (`node_modules/`, `*.tmp`, `private/**`).
+pub const MEDIA_IGNORE_FILE: &str = ".mediaignore";
+
+/// Lists the media files under `root`. `.mediaignore` files anywhere between `root` and an
+/// entry apply to it (files above `root` don't); ignored directories are never descended.
+/// Hidden entries are skipped; .gitignore and friends are not consulted.
+pub fn scan_directory(root: &str) -> Result<Vec<LoadedFile>, tauri::Error> {
+    if !Path::new(root).is_dir() {
+        return Err(ioerr(format!("{root} is not a directory")));
+    }
+    let walker = WalkBuilder::new(root)
+        .standard_filters(false)
+        .hidden(true)
+        .parents(false)
+        .add_custom_ignore_filename(MEDIA_IGNORE_FILE)
+        .build();
+
+    let mut files = vec![];
+    for entry in walker {
+        let entry = match entry {
+            Ok(e) => e,
+            Err(e) => {
+                log::warn!("[scan] {e}");
+                continue;
+            }
+        };
+        if !entry.file_type().is_some_and(|t| t.is_file()) { continue; }
+        let name = entry.file_name().to_string_lossy().to_string();
+        if matches!(get_type(&name), FileType::Other) { continue; }
+        files.push(LoadedFile { name, path: entry.path().to_string_lossy().to_string() });
+    }
+    Ok(files)
+}
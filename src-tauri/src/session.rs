@@ -0,0 +1,76 @@
+use std::io::Write;
+use std::path::Path;
+use std::time::SystemTime;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::analyse::{ioerr, sys_time_to_rfc3339};
+use crate::migrate::{migrate_analysis, ANALYSIS_SCHEMA_VERSION};
+use crate::types::*;
+
+// -----------------------------------------------------------------------------
+// Session files: a whole scan saved as one JSON document
+// -----------------------------------------------------------------------------
+
+/// Bump when the document (or a serialized analysis type) changes incompatibly, and teach
+/// `import_analyses` to migrate the older shape.
+pub const SESSION_SCHEMA_VERSION: u32 = 1;
+
+pub fn export_analyses(files: Vec<AnalysedLoadedFile>, path: &str) -> Result<(), tauri::Error> {
+    let session = AnalysisSession {
+        schema_version: SESSION_SCHEMA_VERSION,
+        exported_at: sys_time_to_rfc3339(Some(SystemTime::now())),
+        files,
+    };
+    let json = serde_json::to_vec(&session).map_err(|e| ioerr(format!("session encode: {e}")))?;
+
+    // Write next to the target and move into place, so a failed export never leaves a
+    // half-written session behind.
+    let dir = Path::new(path).parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+    tmp.write_all(&json)?;
+    tmp.persist(path).map_err(|e| ioerr(format!("session write {path}: {}", e.error)))?;
+    Ok(())
+}
+
+// `AnalysisSession` as stored: each analysis stays raw JSON until `migrate_analysis` has
+// brought it up to the current `MediaAnalysis` shape.
+#[derive(Deserialize)]
+struct StoredSession {
+    schema_version: u32,
+    exported_at: Option<String>,
+    files: Vec<StoredFile>,
+}
+
+#[derive(Deserialize)]
+struct StoredFile {
+    name: String,
+    path: String,
+    #[serde(default)]
+    analysis: Option<Value>,
+}
+
+pub fn import_analyses(path: &str) -> Result<AnalysisSession, tauri::Error> {
+    let data = std::fs::read(path)?;
+    let session: StoredSession = serde_json::from_slice(&data)
+        .map_err(|e| ioerr(format!("session parse {path}: {e}")))?;
+    if session.schema_version > SESSION_SCHEMA_VERSION {
+        return Err(ioerr(format!(
+            "{path} was written by a newer version (schema {}, this build reads up to {})",
+            session.schema_version, SESSION_SCHEMA_VERSION
+        )));
+    }
+    let files = session.files
+        .into_iter()
+        .map(|f| {
+            let analysis = match f.analysis {
+                Some(value) => migrate_analysis(value, ANALYSIS_SCHEMA_VERSION)
+                    .map_err(|e| ioerr(format!("session {path}: {}: {e}", f.path)))?,
+                None => MediaAnalysis::default(),
+            };
+            Ok(AnalysedLoadedFile { name: f.name, path: f.path, analysis })
+        })
+        .collect::<Result<_, tauri::Error>>()?;
+    Ok(AnalysisSession { schema_version: session.schema_version, exported_at: session.exported_at, files })
+}
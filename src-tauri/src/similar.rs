@@ -0,0 +1,30 @@
+use crate::analyse::{ioerr, phash_b64};
+use crate::types::*;
+
+// -----------------------------------------------------------------------------
+// Similar media: reverse-image lookup of a reference file against analysed phashes
+// -----------------------------------------------------------------------------
+
+/// The 64-bit value behind a `phash_b64` string.
+fn phash_bits(b64: &str) -> Option<u64> {
+    let bytes: [u8; 8] = base64::decode(b64).ok()?.try_into().ok()?;
+    Some(u64::from_be_bytes(bytes))
+}
+
+/// Analyses whose image phash (or a video's first-keyframe phash) is within `max_distance`
+/// bits of the reference image's, closest first; ties keep input order. Only Thorough
+/// analyses carry a phash, so anything analysed with a lighter profile never matches.
+pub fn find_similar_media(reference_path: &str, analyses: &[MediaAnalysis], max_distance: u32) -> Result<Vec<SimilarMatch>, tauri::Error> {
+    let img = image::open(reference_path).map_err(|e| ioerr(format!("{reference_path}: {e}")))?;
+    let reference = phash_bits(&phash_b64(&img)).unwrap_or_default();
+
+    let mut matches: Vec<SimilarMatch> = analyses.iter().enumerate()
+        .filter_map(|(index, a)| {
+            let hash = a.image.phash.as_deref().or(a.video.phash.as_deref()).and_then(phash_bits)?;
+            let distance = (hash ^ reference).count_ones();
+            (distance <= max_distance).then_some(SimilarMatch { index, distance })
+        })
+        .collect();
+    matches.sort_by_key(|m| (m.distance, m.index));
+    Ok(matches)
+}
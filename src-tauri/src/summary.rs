@@ -0,0 +1,39 @@
+use std::collections::HashMap;
+
+use crate::types::*;
+
+// -----------------------------------------------------------------------------
+// Batch summary: dashboard aggregates over finished analyses (no I/O, no AI call)
+// -----------------------------------------------------------------------------
+
+/// Tags and topics listed in a summary, most common first.
+const SUMMARY_TOP_TAGS: usize = 20;
+
+pub fn summarize_analyses(analyses: &[MediaAnalysis]) -> BatchSummary {
+    let mut summary = BatchSummary { file_count: analyses.len(), ..Default::default() };
+    for a in analyses {
+        *summary.counts_by_type.entry(a.meta.file_type.as_str().to_string()).or_default() += 1;
+        summary.total_bytes += a.meta.size_bytes.unwrap_or(0);
+        summary.total_video_duration_sec += a.video.duration_sec.unwrap_or(0.0);
+        if a.tagging.ai_enriched { summary.ai_enriched += 1; } else { summary.degraded += 1; }
+    }
+    summary.top_tags = top_counts(analyses.iter().map(|a| &a.tagging.tags));
+    summary.top_topics = top_counts(analyses.iter().map(|a| &a.tagging.topics));
+    summary
+}
+
+/// Counts each tag once per file, case-insensitively; ties are alphabetical so the
+/// dashboard doesn't reshuffle between calls.
+fn top_counts<'a>(per_file: impl Iterator<Item = &'a Vec<TagWithConfidence>>) -> Vec<TagCount> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for tags in per_file {
+        let mut seen: Vec<String> = tags.iter().map(|t| t.tag.trim().to_lowercase()).filter(|t| !t.is_empty()).collect();
+        seen.sort();
+        seen.dedup();
+        for t in seen { *counts.entry(t).or_default() += 1; }
+    }
+    let mut top: Vec<TagCount> = counts.into_iter().map(|(tag, count)| TagCount { tag, count }).collect();
+    top.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+    top.truncate(SUMMARY_TOP_TAGS);
+    top
+}
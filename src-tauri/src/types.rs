@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::{fs, path::Path, process::Command};
 use std::io::Read;
 use std::time::SystemTime;
@@ -15,28 +16,293 @@ macro_rules! analysis {
         pub struct $name {
             $( $field_vis $field: $ty ),*
         }
+
+        impl $crate::types::Merge for $name {
+            fn merge(self, overlay: Self) -> Self {
+                Self { $( $field: $crate::types::Merge::merge(self.$field, overlay.$field) ),* }
+            }
+        }
     };
 }
 
+/// Field-wise combination of two partial results for the same file: whatever the overlay
+/// actually filled in wins, defaults never erase what the base already knows.
+pub trait Merge {
+    fn merge(self, overlay: Self) -> Self;
+}
+
+impl<T> Merge for Option<T> {
+    fn merge(self, overlay: Self) -> Self { overlay.or(self) }
+}
+
+impl Merge for bool {
+    fn merge(self, overlay: Self) -> Self { self || overlay }
+}
+
+impl Merge for u32 {
+    fn merge(self, overlay: Self) -> Self { if overlay != 0 { overlay } else { self } }
+}
+
+impl Merge for f32 {
+    fn merge(self, overlay: Self) -> Self { if overlay != 0.0 { overlay } else { self } }
+}
+
+impl Merge for String {
+    fn merge(self, overlay: Self) -> Self { if overlay.is_empty() { self } else { overlay } }
+}
+
+// Keywords, colors, warnings: union, base order first.
+impl Merge for Vec<String> {
+    fn merge(mut self, overlay: Self) -> Self {
+        for s in overlay {
+            if !self.contains(&s) { self.push(s); }
+        }
+        self
+    }
+}
+
+// Union by tag name (case-insensitive), keeping the higher confidence.
+impl Merge for Vec<TagWithConfidence> {
+    fn merge(mut self, overlay: Self) -> Self {
+        for t in overlay {
+            match self.iter_mut().find(|x| x.tag.eq_ignore_ascii_case(&t.tag)) {
+                Some(x) => x.confidence = x.confidence.max(t.confidence),
+                None => self.push(t),
+            }
+        }
+        self
+    }
+}
+
+// Structural lists (page sizes, outline) describe the file as a whole: replace, don't mix.
+impl Merge for Vec<(u32, u32)> {
+    fn merge(self, overlay: Self) -> Self { if overlay.is_empty() { self } else { overlay } }
+}
+impl Merge for Vec<(f64, f64)> {
+    fn merge(self, overlay: Self) -> Self { if overlay.is_empty() { self } else { overlay } }
+}
+
+impl Merge for Vec<PdfOutlineEntry> {
+    fn merge(self, overlay: Self) -> Self { if overlay.is_empty() { self } else { overlay } }
+}
+
+impl Merge for Vec<PdfFont> {
+    fn merge(self, overlay: Self) -> Self { if overlay.is_empty() { self } else { overlay } }
+}
+
+impl Merge for Vec<DominantColor> {
+    fn merge(self, overlay: Self) -> Self { if overlay.is_empty() { self } else { overlay } }
+}
+
+impl Merge for Vec<EmailAttachment> {
+    fn merge(self, overlay: Self) -> Self { if overlay.is_empty() { self } else { overlay } }
+}
+
+impl Merge for Vec<VideoChapter> {
+    fn merge(self, overlay: Self) -> Self { if overlay.is_empty() { self } else { overlay } }
+}
+
+impl Merge for Vec<SubtitleTrack> {
+    fn merge(self, overlay: Self) -> Self { if overlay.is_empty() { self } else { overlay } }
+}
+
+impl Merge for BTreeMap<String, String> {
+    fn merge(mut self, overlay: Self) -> Self {
+        self.extend(overlay);
+        self
+    }
+}
+
+impl Merge for MetaFileType {
+    fn merge(self, overlay: Self) -> Self { if overlay == MetaFileType::Other { self } else { overlay } }
+}
+
+impl Merge for Suggested {
+    fn merge(self, overlay: Self) -> Self {
+        if overlay.rename.is_empty() && overlay.folder.is_empty() { self } else { overlay }
+    }
+}
+
 pub enum FileType {
     Pdf,
     Image,
+    /// Camera RAW (CR2, NEF, ARW, DNG, ...). Reported as `image`; `image` can't decode these.
+    RawImage,
+    /// Reported as `image`; metadata comes from the XML, see [`SVGMeta`].
+    Svg,
+    /// docx / xlsx / pptx; metadata only, see [`OfficeMeta`].
+    Office,
+    /// RFC 822 message (.eml); headers and attachment list, see [`EmailMeta`].
+    Email,
     Video,
     Other,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct LoadedFile {
     pub name: String,
     pub path: String,
 }
 
+/// File contents without a filesystem path (drag data, a remote fetch). `name` supplies the
+/// extension the pipeline dispatches on.
+#[derive(Debug, Deserialize)]
+pub struct LoadedBytes {
+    pub name: String,
+    pub data_b64: String,
+}
+
+/// A loaded file together with its analysis, as the frontend holds them after analysing.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AnalysedLoadedFile {
+    pub name: String,
+    pub path: String,
+    #[serde(default)]
+    pub analysis: MediaAnalysis,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AnalyseOptions {
+    /// Images/unknown files above this size get metadata only (no full decode).
+    pub max_decode_bytes: u64,
+    /// Never call the AI endpoint; tags/topics are derived from local keywords and
+    /// numeric facts instead. Lower semantic quality, but deterministic and air-gap safe.
+    pub offline: bool,
+    /// Max concurrent ffprobe/ffmpeg/pdftoppm processes for this job (each batch, watcher
+    /// event or single-file call is one).
+    pub max_external_processes: usize,
+    /// Attach the base64 previews sent to the AI to each result (large; off by default).
+    pub return_previews: bool,
+    /// How much work to do per file; see [`AnalysisProfile`].
+    pub profile: AnalysisProfile,
+    /// Encoding of the previews sent to the AI (image, video frames, PDF page); see [`PreviewFormat`].
+    pub preview_format: PreviewFormat,
+    /// JPEG quality (1-100) for image previews. The WebP encoder is lossless and ignores it.
+    pub preview_quality: u8,
+    /// Local metadata only: no previews (so no ffmpeg/pdftoppm runs or full decodes) and no
+    /// AI call. Meant for a fast first indexing pass; tags are derived locally as when offline.
+    pub skip_previews: bool,
+    /// Free-text tagging style for the AI ("single-word tags only", "use taxonomy X").
+    /// Sent as `instructions` to plain endpoints and appended to the system prompt for
+    /// OpenAI, Anthropic and Ollama. Empty: none.
+    pub instructions: String,
+    /// Keep GPS coordinates out of the AI request. They are still extracted and returned
+    /// in `Image`; only the outbound payload loses them.
+    pub redact_location: bool,
+    /// How many dominant colours to report per image (0: none).
+    pub color_count: usize,
+    /// Notation of `Image::dominant_colors` values; see [`ColorFormat`].
+    pub color_format: ColorFormat,
+}
+
+/// Depth of analysis. Sent by the frontend as `"quick"`, `"standard"` or `"thorough"`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnalysisProfile {
+    /// Filename and header dimensions only: no external tools, no AI (tags are derived locally).
+    Quick,
+    #[default]
+    Standard,
+    /// Standard plus pHash, barcode scan, every EXIF field, PDF text, and larger AI previews.
+    Thorough,
+}
+
+/// Applies to image previews, video frames and PDF rasters. `"auto"` picks PNG for images
+/// with transparent pixels and for PDF pages (line art), JPEG otherwise.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreviewFormat {
+    #[default]
+    Auto,
+    Png,
+    Jpeg,
+    Webp,
+}
+
+/// Sent by the frontend as `"hex"` (`#aabbcc`), `"rgb"` (`rgb(170, 187, 204)`) or `"hsl"`
+/// (`hsl(210, 25%, 73%)`).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorFormat {
+    #[default]
+    Hex,
+    Rgb,
+    Hsl,
+}
+
+// Default for `AnalyseOptions.preview_format`, from TAGGER_PREVIEW_FORMAT
+// (auto|jpeg|png|webp); requests that set the option override it.
+static PREVIEW_FORMAT_ENV: Lazy<PreviewFormat> = Lazy::new(|| {
+    let Ok(raw) = std::env::var("TAGGER_PREVIEW_FORMAT") else { return PreviewFormat::Auto; };
+    serde_json::from_value(serde_json::Value::String(raw.trim().to_ascii_lowercase())).unwrap_or_else(|_| {
+        log::warn!("[analyse] ignoring TAGGER_PREVIEW_FORMAT={raw:?}: expected auto, jpeg, png or webp");
+        PreviewFormat::Auto
+    })
+});
+
+impl Default for AnalyseOptions {
+    fn default() -> Self {
+        Self {
+            max_decode_bytes: 256 * 1024 * 1024,
+            offline: false,
+            max_external_processes: 4,
+            return_previews: false,
+            profile: AnalysisProfile::Standard,
+            preview_format: *PREVIEW_FORMAT_ENV,
+            preview_quality: 80,
+            skip_previews: false,
+            instructions: String::new(),
+            redact_location: false,
+            color_count: 5,
+            color_format: ColorFormat::Hex,
+        }
+    }
+}
+
+/// Serialized form of `Metadata.file_type`. The wire values are the same lowercase strings
+/// the field used to carry ("image", "video", "pdf", "other", plus "audio", "office" and "email"), but stored
+/// results with any other value will no longer deserialize.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetaFileType {
+    Image,
+    Video,
+    Pdf,
+    Audio,
+    Office,
+    Email,
+    #[default]
+    Other,
+}
+
+impl MetaFileType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MetaFileType::Image => "image",
+            MetaFileType::Video => "video",
+            MetaFileType::Pdf => "pdf",
+            MetaFileType::Audio => "audio",
+            MetaFileType::Office => "office",
+            MetaFileType::Email => "email",
+            MetaFileType::Other => "other",
+        }
+    }
+}
+
 analysis!(Metadata {
-    pub file_type: String,
+    pub file_type: MetaFileType,
     pub mime: Option<String>,
     pub size_bytes: Option<u64>,
     pub created_at: Option<String>,
     pub modified_at: Option<String>,
+    // Last read; stale on noatime mounts. Read before analysis opens the file.
+    pub accessed_at: Option<String>,
+    pub is_corrupt: Option<bool>,
+    pub detected_language: Option<String>,
+    // 0-100 from resolution + sharpness (images), resolution + bitrate (videos) or scan DPI
+    // (PDFs); None when the signals weren't gathered (Quick, no decode, no ffprobe).
+    pub quality_score: Option<f32>,
 });
 
 analysis!(Video {
@@ -45,36 +311,360 @@ analysis!(Video {
     pub duration_sec: Option<f64>,
     pub fps: Option<f64>,
     pub codec: Option<String>,
+    pub recorded_at: Option<String>,
+    pub is_hdr: bool,
+    pub pix_fmt: Option<String>,
+    pub color_space: Option<String>,
+    pub color_transfer: Option<String>,
+    pub color_primaries: Option<String>,
+    pub has_decode_errors: bool,
+    // Image::phash of the first extracted keyframe (Thorough only).
+    pub phash: Option<String>,
+    pub chapters: Vec<VideoChapter>,
+    pub subtitle_tracks: Vec<SubtitleTrack>,
+    // fps above 60 (or an Android capture rate above playback); time-lapse needs the
+    // Android capture-rate tag, other cameras don't record one.
+    pub is_slow_motion: bool,
+    pub is_timelapse: bool,
+    // Spherical side data, a 2:1 frame or a "360"/"panorama" filename.
+    pub is_360: bool,
 });
 
+/// A container chapter (MP4 chapter track, MKV chapter atom); `title` may be empty.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct VideoChapter { pub title: String, pub start_sec: f64, pub end_sec: f64 }
+
+/// An embedded subtitle stream. `language` is the stream's ISO 639 tag, None when untagged.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct SubtitleTrack { pub language: Option<String>, pub codec: Option<String>, pub is_forced: bool }
+
 analysis!(PDF {
     pub page_count: Option<u32>,
     pub page0_width_pt: Option<f64>,
     pub page0_height_pt: Option<f64>,
+    pub all_page_dimensions: Vec<(f64, f64)>,
+    pub has_mixed_page_sizes: bool,
+    pub outline: Vec<PdfOutlineEntry>,
+    pub ocr_text: Option<String>,
+    pub text_excerpt: Option<String>,
+    // Whole text layer with text extraction (Thorough), else the OCR'd first page when OCR
+    // runs; chars exclude whitespace.
+    pub word_count: Option<u32>,
+    pub char_count: Option<u32>,
+    // From each page's /Resources /Font, deduplicated across pages.
+    pub fonts: Vec<PdfFont>,
+    // Effective DPI of the page-0 preview (raster pixels over page inches); None without one.
+    pub preview_dpi: Option<f64>,
 });
 
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct PdfOutlineEntry { pub title: String, pub children: Vec<PdfOutlineEntry> }
+
+/// A font a PDF uses. Not embedded means the viewer or RIP substitutes one.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PdfFont { pub name: String, pub subtype: String, pub is_embedded: bool }
+
 analysis!(Image {
     pub width: Option<u32>,
     pub height: Option<u32>,
     pub exif_datetime: Option<String>,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub lens_make: Option<String>,
+    pub lens_model: Option<String>,
+    // EXIF Software, e.g. "Adobe Photoshop Lightroom Classic 13.0"; firmware on camera originals.
+    pub software: Option<String>,
     pub phash: Option<String>,
-    pub dominant_colors: Vec<String>,
+    // AnalyseOptions::color_count colours in AnalyseOptions::color_format, most common first;
+    // empty when pixels weren't decoded.
+    pub dominant_colors: Vec<DominantColor>,
+    pub is_grayscale: Option<bool>,
+    // Nominal alpha channel, from the header (RGBA/LA colour types).
+    pub has_alpha: bool,
+    // Some alpha value below 255 (on a downscale); None when pixels weren't decoded.
+    pub has_transparency: Option<bool>,
+    // "file_not_found" | "not_an_image" | "truncated_file" | "unsupported_format"
+    pub validation_error: Option<String>,
+    // Format by content signature ("jpeg", "png", "webp", ...); None when unrecognised.
+    pub actual_format: Option<String>,
+    // The extension names a different format than actual_format (a JPEG saved as .png).
+    pub extension_mismatch: bool,
+    pub is_screenshot: Option<bool>,
+    pub screenshot_reason: Option<String>,
+    pub ocr_text: Option<String>,
+    pub exif: BTreeMap<String, String>,
+    // From the preview decode; None when pixels weren't decoded (Quick, skip_previews, offline
+    // without the `ocr` feature).
+    pub histogram: Option<Histogram>,
+    pub barcodes: Vec<String>,
+    // Decimal degrees from the EXIF GPS IFD; south and west are negative.
+    pub gps_lat: Option<f64>,
+    pub gps_lon: Option<f64>,
+    // Laplacian variance of a grayscale downscale; lower is blurrier. Same decode as histogram.
+    pub sharpness: Option<f32>,
+    // 0 (clean) .. 1 (very noisy), from residuals in smooth regions. Both None with
+    // TAGGER_SKIP_QUALITY=1.
+    pub noise_level: Option<f64>,
+    // XMP GPano, "360" in EXIF Make/Software, a 2:1 frame or a "360"/"panorama" filename.
+    pub is_360: bool,
+    // Set by `detect_bursts` for the batch it is given, never by analyse_single.
+    pub is_burst_member: bool,
+    // Shared by numbered shots taken seconds apart; set by `detect_sequences` like the above.
+    pub sequence_id: Option<u32>,
+    // TIFF only: every page's pixel size; previews and pixel facts describe page 1.
+    pub page_count: Option<u32>,
+    pub page_dimensions: Vec<(u32, u32)>,
+    // Pixels per inch from EXIF XResolution/YResolution or the JFIF header; centimetre
+    // units are converted. None when the file doesn't record one.
+    pub dpi_x: Option<f64>,
+    pub dpi_y: Option<f64>,
 });
 
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct DominantColor {
+    pub value: String,
+    // Approximate share of the image, 0-100.
+    pub percent: f32,
+}
+
+/// Per-channel pixel counts, 64 bins each (bin = value / 4).
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Histogram { pub r: Vec<u32>, pub g: Vec<u32>, pub b: Vec<u32> }
+
+// Root <svg> attributes as written (units kept, e.g. "210mm"), plus its <title>/<desc>.
+analysis!(SVGMeta {
+    pub view_box: Option<String>,
+    pub width: Option<String>,
+    pub height: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub element_count: Option<u32>,
+});
+
+// From docProps/core.xml (title, author) and docProps/app.xml (app_name); the counts come
+// from the main part of whichever kind of document it is.
+analysis!(OfficeMeta {
+    pub app_name: Option<String>,
+    pub author: Option<String>,
+    pub title: Option<String>,
+    pub word_count: Option<u32>,
+    pub slide_count: Option<u32>,
+    pub sheet_count: Option<u32>,
+});
+
+// Headers are decoded (RFC 2047); `date` is RFC3339 when it parses, else as written.
+analysis!(EmailMeta {
+    pub subject: Option<String>,
+    pub from: Option<String>,
+    pub to: Vec<String>,
+    pub date: Option<String>,
+    pub has_attachments: bool,
+    pub attachment_count: u32,
+    pub attachments: Vec<EmailAttachment>,
+});
+
+/// Listed from the MIME structure only; the content is never decoded.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct EmailAttachment { pub name: String, pub mime: String }
+
 analysis!(Tagging {
-    pub tags: Vec<String>,
-    pub topics: Vec<String>,
+    pub tags: Vec<TagWithConfidence>,
+    pub topics: Vec<TagWithConfidence>,
     pub raw_keywords: Vec<String>,
+    // raw_keywords before stemming (TAGGER_KEYWORD_STEMMING); empty when stemming is off.
+    pub unstemmed_keywords: Vec<String>,
+    // The AI endpoint answered; false when tags were derived locally (offline, Quick,
+    // skip_previews, no endpoint) or the call failed.
+    pub ai_enriched: bool,
+    // The TAGGER_ENDPOINT entry that answered, without credentials; None when not enriched.
+    pub ai_endpoint: Option<String>,
 });
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "TagRepr")]
+pub struct TagWithConfidence { pub tag: String, pub confidence: f32 }
+
+// Older endpoints (and older stored results) send plain strings; accept both shapes.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TagRepr {
+    Scored { tag: String, #[serde(default = "full_confidence")] confidence: f32 },
+    Plain(String),
+}
+
+fn full_confidence() -> f32 { 1.0 }
+
+impl From<TagRepr> for TagWithConfidence {
+    fn from(r: TagRepr) -> Self {
+        match r {
+            TagRepr::Scored { tag, confidence } => TagWithConfidence { tag, confidence },
+            TagRepr::Plain(tag) => TagWithConfidence::from(tag),
+        }
+    }
+}
+
+impl From<String> for TagWithConfidence {
+    fn from(tag: String) -> Self { TagWithConfidence { tag, confidence: 1.0 } }
+}
+
 analysis!(MediaAnalysis {
+    // See migrate::ANALYSIS_SCHEMA_VERSION; set once analysis finishes.
+    pub schema_version: u32,
     pub meta: Metadata,
     pub video: Video,
     pub pdf: PDF,
     pub image: Image,
+    pub svg: SVGMeta,
+    pub office: OfficeMeta,
+    pub email: EmailMeta,
     pub tagging: Tagging,
-    pub suggested: Suggested
+    pub suggested: Suggested,
+    pub warnings: Vec<String>,
+    pub previews: Option<MediaPreviews>,
+    // Why analysis failed, from the batch commands; every other field is then left empty.
+    pub error: Option<String>,
+});
+
+impl MediaAnalysis {
+    /// Combines a quick pass (`base`) with a later, partial one (`overlay`, e.g. the AI pass)
+    /// without re-running anything. See [`Merge`] for the per-field rules.
+    pub fn merge(base: MediaAnalysis, overlay: MediaAnalysis) -> MediaAnalysis {
+        Merge::merge(base, overlay)
+    }
+}
+
+// What the AI was shown; only attached to results when `return_previews` is set.
+analysis!(MediaPreviews {
+    pub image_b64: Option<String>,
+    pub image_mime: Option<String>,
+    pub video_frames_b64: Option<Vec<String>>,
+    pub video_frames_mime: Option<String>,
+    pub pdf_page0_b64: Option<String>,
+    pub pdf_page0_mime: Option<String>,
 });
 
 #[derive(Debug, Default, Deserialize, Serialize)]
-pub struct Suggested { pub rename: String, pub reason: String, pub confidence: f32 }
+pub struct Suggested {
+    pub rename: String,
+    pub reason: String,
+    pub confidence: f32,
+    /// Relative destination directory, e.g. `Invoices/2024`; empty when none (or unsafe).
+    #[serde(default)]
+    pub folder: String,
+    #[serde(default)]
+    pub folder_reason: String,
+    /// Lower-confidence suggestions, best first; empty when the AI offered only one.
+    #[serde(default)]
+    pub alternatives: Vec<Suggested>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SpriteSheet {
+    pub image_b64: Option<String>,
+    pub columns: u32,
+    pub rows: u32,
+    pub tile_width: u32,
+    pub timestamps_sec: Vec<f64>,
+}
+
+/// One entry of a dry-run rename. `collision` is set when another entry targets the same
+/// path, or the target already exists on disk.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RenamePlan {
+    pub old_path: String,
+    pub new_path: String,
+    pub collision: bool,
+}
+
+/// Result of one entry of `execute_renames`. After a rollback every entry reports `ok: false`.
+#[derive(Debug, Serialize)]
+pub struct RenameOutcome {
+    pub old_path: String,
+    pub new_path: String,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+/// A saved scan (see `session.rs`).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AnalysisSession {
+    pub schema_version: u32,
+    pub exported_at: Option<String>,
+    pub files: Vec<AnalysedLoadedFile>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Md5,
+}
+
+/// Result of `verify_file_integrity`; both hashes are lowercase hex.
+#[derive(Debug, Serialize)]
+pub struct IntegrityCheck {
+    pub matches: bool,
+    pub actual_hash: String,
+    pub expected_hash: String,
+}
+
+/// Output of `detect_private_data` for one file.
+#[derive(Debug, Serialize)]
+pub struct PrivacyReport {
+    pub path: String,
+    pub has_gps: bool,
+    pub has_face_hint: bool,
+    pub recommendations: Vec<String>,
+}
+
+/// One burst from `detect_bursts`: indices into the analyses passed in, in capture order.
+#[derive(Debug, Serialize)]
+pub struct BurstSequence {
+    pub members: Vec<usize>,
+    pub best: usize,
+}
+
+/// Output of `detect_bursts`: the analyses with `is_burst_member` set, and the bursts.
+#[derive(Debug, Serialize)]
+pub struct BurstReport {
+    pub analyses: Vec<MediaAnalysis>,
+    pub bursts: Vec<BurstSequence>,
+}
+
+/// One hit from `find_similar`: an index into the analyses passed in and how many of the
+/// 64 phash bits differ from the reference (0 = same picture).
+#[derive(Debug, Serialize)]
+pub struct SimilarMatch {
+    pub index: usize,
+    pub distance: u32,
+}
+
+/// Output of `summarize`: aggregates over a batch of analyses for the dashboard.
+#[derive(Debug, Default, Serialize)]
+pub struct BatchSummary {
+    pub file_count: usize,
+    // MetaFileType::as_str -> count
+    pub counts_by_type: BTreeMap<String, usize>,
+    pub total_bytes: u64,
+    pub total_video_duration_sec: f64,
+    // Most common first; a tag counts once per file.
+    pub top_tags: Vec<TagCount>,
+    pub top_topics: Vec<TagCount>,
+    pub ai_enriched: usize,
+    pub degraded: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+/// Result of `write_image_tags` for one file; a skipped file is left unchanged.
+#[derive(Debug, Serialize)]
+pub struct TagWriteOutcome {
+    pub path: String,
+    pub written: bool,
+    pub skipped_reason: Option<String>,
+}
@@ -0,0 +1,55 @@
+// -----------------------------------------------------------------------------
+// File names: what Windows, macOS and Linux all accept
+// -----------------------------------------------------------------------------
+
+/// Most filesystems cap a single name component at 255 bytes.
+const MAX_FILENAME_BYTES: usize = 255;
+/// Extensions up to this long are kept whole when a name has to be shortened.
+const MAX_KEPT_EXTENSION_BYTES: usize = 16;
+
+/// Forbidden in Windows file names (plus control characters); `/` and `\` also in paths.
+fn is_illegal(c: char) -> bool {
+    matches!(c, '\\' | '/' | ':' | '*' | '?' | '"' | '<' | '>' | '|') || c.is_control()
+}
+
+fn trim_name(s: &str) -> &str {
+    s.trim_matches(|c: char| c == ' ' || c == '.')
+}
+
+/// Drops illegal characters, collapses space runs, trims leading/trailing dots and spaces
+/// (Windows refuses trailing ones) and caps the result at 255 bytes on a character
+/// boundary, shortening the stem rather than the extension. May return "".
+pub fn sanitize_filename(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for c in name.chars().filter(|&c| !is_illegal(c)) {
+        if c == ' ' && out.ends_with(' ') { continue; }
+        out.push(c);
+    }
+    let out = trim_name(&out);
+    if out.len() <= MAX_FILENAME_BYTES {
+        return out.to_string();
+    }
+
+    let ext = out.rfind('.').map(|i| &out[i..]).filter(|e| e.len() <= MAX_KEPT_EXTENSION_BYTES).unwrap_or("");
+    let mut cut = MAX_FILENAME_BYTES - ext.len();
+    while !out.is_char_boundary(cut) { cut -= 1; }
+    format!("{}{ext}", trim_name(&out[..cut]))
+}
+
+/// Why `name` can't be used as a file name as is, for rename plans the caller may have
+/// edited. `sanitize_filename` output always passes unless it is empty.
+pub fn validate_filename(name: &str) -> Result<(), String> {
+    if trim_name(name).is_empty() {
+        return Err(format!("{name:?} is not a usable file name"));
+    }
+    if let Some(c) = name.chars().find(|&c| is_illegal(c)) {
+        return Err(format!("{name:?} contains {c:?}, which is not allowed in file names"));
+    }
+    if name.starts_with([' ', '.']) || name.ends_with([' ', '.']) {
+        return Err(format!("{name:?} starts or ends with a dot or space"));
+    }
+    if name.len() > MAX_FILENAME_BYTES {
+        return Err(format!("{name:?} is longer than {MAX_FILENAME_BYTES} bytes"));
+    }
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use tauri::Emitter;
+
+use crate::analyse::{analyse_single, get_type, ioerr};
+use crate::job;
+use crate::types::*;
+
+// -----------------------------------------------------------------------------
+// Directory watcher: analyse files as they appear or change
+// -----------------------------------------------------------------------------
+
+/// Editors and copy tools write in several steps; wait this long after the last event.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Payload of the `file_analysed` event.
+#[derive(Serialize)]
+pub struct FileAnalysedEvent {
+    pub name: String,
+    pub path: String,
+    pub analysis: MediaAnalysis,
+}
+
+/// Tauri-managed slot for the (single) running watcher.
+#[derive(Default)]
+pub struct WatcherState(pub Mutex<Option<FileWatcher>>);
+
+pub struct FileWatcher {
+    watcher: RecommendedWatcher,
+    worker: JoinHandle<()>,
+}
+
+impl FileWatcher {
+    /// Dropping the watcher closes the event channel; the worker finishes the file it is
+    /// on (if any) and exits.
+    pub fn stop(self) {
+        drop(self.watcher);
+        let _ = self.worker.join();
+    }
+}
+
+pub fn start_watcher(app: tauri::AppHandle, dir: &str, opts: AnalyseOptions) -> Result<FileWatcher, tauri::Error> {
+    let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
+    let mut watcher = notify::recommended_watcher(tx).map_err(|e| ioerr(format!("watcher: {e}")))?;
+    watcher.watch(Path::new(dir), RecursiveMode::Recursive).map_err(|e| ioerr(format!("watch {dir}: {e}")))?;
+
+    let worker = std::thread::spawn(move || {
+        // Path -> time of its latest event; analysed once quiet for WATCH_DEBOUNCE.
+        let mut pending: HashMap<PathBuf, Instant> = HashMap::new();
+        loop {
+            let wait = pending.values().map(|t| (*t + WATCH_DEBOUNCE).saturating_duration_since(Instant::now())).min();
+            let msg = match wait {
+                Some(d) => rx.recv_timeout(d),
+                None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match msg {
+                Ok(Ok(ev)) if matches!(ev.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                    for p in ev.paths.into_iter().filter(|p| is_watched_file(p)) {
+                        pending.insert(p, Instant::now());
+                    }
+                }
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => log::warn!("[watch] {e}"),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            let ready: Vec<PathBuf> = pending.iter()
+                .filter(|(_, t)| t.elapsed() >= WATCH_DEBOUNCE)
+                .map(|(p, _)| p.clone())
+                .collect();
+            for p in ready {
+                pending.remove(&p);
+                analyse_and_emit(&app, &p, &opts);
+            }
+        }
+    });
+
+    Ok(FileWatcher { watcher, worker })
+}
+
+/// Regular, non-hidden files of a type `get_type` knows.
+fn is_watched_file(p: &Path) -> bool {
+    let Some(name) = p.file_name().and_then(|n| n.to_str()) else { return false; };
+    !name.starts_with('.') && !matches!(get_type(name), FileType::Other)
+}
+
+fn analyse_and_emit(app: &tauri::AppHandle, p: &Path, opts: &AnalyseOptions) {
+    // Gone again (temp file) or a directory named like a file.
+    if !p.is_file() { return; }
+    let file = LoadedFile {
+        name: p.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+        path: p.to_string_lossy().to_string(),
+    };
+    let (name, path) = (file.name.clone(), file.path.clone());
+    let job_id = job::next_id();
+    let _job = job::JobScope::enter(job_id);
+    log::info!("[watch job={job_id}] analysing {path}");
+    match analyse_single(file, opts) {
+        Ok(analysis) => {
+            let event = FileAnalysedEvent { name, path, analysis };
+            if let Err(e) = app.emit("file_analysed", &event) {
+                log::warn!("[watch job={job_id}] emit failed for {}: {e}", event.path);
+            }
+        }
+        Err(e) => log::warn!("[watch job={job_id}] analysis failed for {path}: {e}"),
+    }
+}